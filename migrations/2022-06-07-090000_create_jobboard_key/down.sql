@@ -0,0 +1,3 @@
+ALTER TABLE jobboard ADD COLUMN key VARCHAR(255);
+
+DROP TABLE IF EXISTS jobboard_key;
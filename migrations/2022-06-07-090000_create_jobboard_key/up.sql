@@ -0,0 +1,12 @@
+CREATE TABLE jobboard_key (
+  key_id VARCHAR(32) PRIMARY KEY,
+  jobboard_id BIGINT REFERENCES jobboard(jobboard_id) ON DELETE CASCADE NOT NULL,
+  key_hash VARCHAR(255) NOT NULL,
+  created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  expires_at TIMESTAMPTZ
+);
+
+CREATE INDEX jobboard_key_jobboard_id_idx ON jobboard_key (jobboard_id);
+
+-- Plaintext keys cannot be carried over: jobboards have to be issued a new key.
+ALTER TABLE jobboard DROP COLUMN key;
@@ -0,0 +1 @@
+ALTER TABLE vacancy DROP COLUMN IF EXISTS search;
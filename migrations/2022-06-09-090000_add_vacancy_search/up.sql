@@ -0,0 +1,10 @@
+-- Vacancies are written in various languages, hence the `simple` configuration which does not stem words. Diesel has
+-- no `tsvector` type, so the column is patched out of its schema and only queried through raw SQL.
+ALTER TABLE vacancy ADD COLUMN search TSVECTOR GENERATED ALWAYS AS (
+  setweight(to_tsvector('simple', coalesce(job_title, '')), 'A')
+    || setweight(to_tsvector('simple', coalesce(skills, '')), 'B')
+    || setweight(to_tsvector('simple', coalesce(responsibilities, '')), 'C')
+    || setweight(to_tsvector('simple', coalesce(description, '')), 'D')
+) STORED;
+
+CREATE INDEX vacancy_search_idx ON vacancy USING GIN (search);
@@ -0,0 +1,5 @@
+DROP TABLE IF EXISTS application_history;
+
+ALTER TABLE application
+  DROP CONSTRAINT IF EXISTS application_status_check,
+  ALTER COLUMN status DROP DEFAULT;
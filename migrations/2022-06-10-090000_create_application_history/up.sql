@@ -0,0 +1,19 @@
+UPDATE application
+SET status = 'received'
+WHERE status NOT IN ('received', 'screening', 'interview', 'offer', 'hired', 'rejected', 'withdrawn');
+
+ALTER TABLE application
+  ALTER COLUMN status SET DEFAULT 'received',
+  ADD CONSTRAINT application_status_check
+    CHECK (status IN ('received', 'screening', 'interview', 'offer', 'hired', 'rejected', 'withdrawn'));
+
+CREATE TABLE application_history (
+  history_id BIGSERIAL PRIMARY KEY,
+  application_id BIGINT REFERENCES application(application_id) ON DELETE CASCADE NOT NULL,
+  from_status VARCHAR(255) NOT NULL,
+  to_status VARCHAR(255) NOT NULL,
+  actor VARCHAR(255) NOT NULL,
+  changed_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX application_history_application_id_idx ON application_history (application_id);
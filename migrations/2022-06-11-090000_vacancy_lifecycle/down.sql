@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS vacancy_published_idx;
+
+ALTER TABLE vacancy
+  DROP CONSTRAINT IF EXISTS vacancy_status_check,
+  ALTER COLUMN status DROP DEFAULT;
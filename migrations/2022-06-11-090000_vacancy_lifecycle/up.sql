@@ -0,0 +1,10 @@
+UPDATE vacancy
+SET status = 'draft'
+WHERE status NOT IN ('draft', 'published', 'paused', 'filled', 'closed', 'expired');
+
+ALTER TABLE vacancy
+  ALTER COLUMN status SET DEFAULT 'draft',
+  ADD CONSTRAINT vacancy_status_check
+    CHECK (status IN ('draft', 'published', 'paused', 'filled', 'closed', 'expired'));
+
+CREATE INDEX vacancy_published_idx ON vacancy (jobboard_id) WHERE status = 'published' AND active AND verified;
@@ -0,0 +1,7 @@
+DROP INDEX IF EXISTS vacancy_expires_at_idx;
+DROP INDEX IF EXISTS vacancy_publish_at_idx;
+
+ALTER TABLE vacancy
+  DROP CONSTRAINT IF EXISTS vacancy_schedule_check,
+  DROP COLUMN publish_at,
+  DROP COLUMN expires_at;
@@ -0,0 +1,8 @@
+ALTER TABLE vacancy
+  ADD COLUMN publish_at TIMESTAMPTZ,
+  ADD COLUMN expires_at TIMESTAMPTZ,
+  ADD CONSTRAINT vacancy_schedule_check CHECK (expires_at > publish_at);
+
+-- Both indexes serve the scheduler, which only looks at vacancies still due to be published or expired.
+CREATE INDEX vacancy_publish_at_idx ON vacancy (publish_at) WHERE status = 'draft';
+CREATE INDEX vacancy_expires_at_idx ON vacancy (expires_at) WHERE status IN ('published', 'paused');
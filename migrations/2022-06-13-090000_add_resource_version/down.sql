@@ -0,0 +1,11 @@
+DROP TRIGGER IF EXISTS bump_version ON application;
+DROP TRIGGER IF EXISTS bump_version ON vacancy;
+DROP TRIGGER IF EXISTS bump_version ON company;
+DROP TRIGGER IF EXISTS bump_version ON jobboard;
+
+ALTER TABLE application DROP COLUMN version;
+ALTER TABLE vacancy DROP COLUMN version;
+ALTER TABLE company DROP COLUMN version;
+ALTER TABLE jobboard DROP COLUMN version;
+
+DROP FUNCTION IF EXISTS bump_version();
@@ -0,0 +1,17 @@
+-- Versions are bumped on every update so that clients can tell whether a resource changed since they fetched it.
+CREATE OR REPLACE FUNCTION bump_version() RETURNS trigger AS $$
+BEGIN
+    NEW.version := OLD.version + 1;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE jobboard ADD COLUMN version INTEGER DEFAULT 1 NOT NULL;
+ALTER TABLE company ADD COLUMN version INTEGER DEFAULT 1 NOT NULL;
+ALTER TABLE vacancy ADD COLUMN version INTEGER DEFAULT 1 NOT NULL;
+ALTER TABLE application ADD COLUMN version INTEGER DEFAULT 1 NOT NULL;
+
+CREATE TRIGGER bump_version BEFORE UPDATE ON jobboard FOR EACH ROW EXECUTE PROCEDURE bump_version();
+CREATE TRIGGER bump_version BEFORE UPDATE ON company FOR EACH ROW EXECUTE PROCEDURE bump_version();
+CREATE TRIGGER bump_version BEFORE UPDATE ON vacancy FOR EACH ROW EXECUTE PROCEDURE bump_version();
+CREATE TRIGGER bump_version BEFORE UPDATE ON application FOR EACH ROW EXECUTE PROCEDURE bump_version();
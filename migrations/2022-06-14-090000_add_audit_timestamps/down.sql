@@ -0,0 +1,9 @@
+DROP TRIGGER IF EXISTS set_updated_at ON application;
+DROP TRIGGER IF EXISTS set_updated_at ON vacancy;
+DROP TRIGGER IF EXISTS set_updated_at ON company;
+DROP TRIGGER IF EXISTS set_updated_at ON jobboard;
+
+ALTER TABLE application DROP COLUMN created_at, DROP COLUMN updated_at;
+ALTER TABLE vacancy DROP COLUMN created_at, DROP COLUMN updated_at;
+ALTER TABLE company DROP COLUMN created_at, DROP COLUMN updated_at;
+ALTER TABLE jobboard DROP COLUMN created_at, DROP COLUMN updated_at;
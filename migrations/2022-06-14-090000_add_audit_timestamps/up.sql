@@ -0,0 +1,32 @@
+ALTER TABLE jobboard
+    ADD COLUMN created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    ADD COLUMN updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL;
+ALTER TABLE company
+    ADD COLUMN created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    ADD COLUMN updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL;
+ALTER TABLE vacancy
+    ADD COLUMN created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    ADD COLUMN updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL;
+ALTER TABLE application
+    ADD COLUMN created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    ADD COLUMN updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP NOT NULL;
+
+-- Existing jobboards and companies keep their original timestamp, without bumping their version.
+ALTER TABLE jobboard DISABLE TRIGGER bump_version;
+UPDATE jobboard SET created_at = timestamp, updated_at = timestamp WHERE timestamp IS NOT NULL;
+ALTER TABLE jobboard ENABLE TRIGGER bump_version;
+
+ALTER TABLE company DISABLE TRIGGER bump_version;
+UPDATE company SET created_at = timestamp, updated_at = timestamp WHERE timestamp IS NOT NULL;
+ALTER TABLE company ENABLE TRIGGER bump_version;
+
+SELECT diesel_manage_updated_at('jobboard');
+SELECT diesel_manage_updated_at('company');
+SELECT diesel_manage_updated_at('vacancy');
+SELECT diesel_manage_updated_at('application');
+
+-- Incremental sync lists the resources updated since a given time.
+CREATE INDEX jobboard_updated_at_idx ON jobboard (updated_at);
+CREATE INDEX company_updated_at_idx ON company (jobboard_id, updated_at);
+CREATE INDEX vacancy_updated_at_idx ON vacancy (jobboard_id, updated_at);
+CREATE INDEX application_updated_at_idx ON application (jobboard_id, updated_at);
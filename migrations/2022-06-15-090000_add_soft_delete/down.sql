@@ -0,0 +1,14 @@
+DROP INDEX IF EXISTS application_deleted_at_idx;
+DROP INDEX IF EXISTS vacancy_deleted_at_idx;
+DROP INDEX IF EXISTS company_deleted_at_idx;
+DROP INDEX IF EXISTS jobboard_deleted_at_idx;
+
+DROP INDEX IF EXISTS company_company_name_key;
+ALTER TABLE company ADD CONSTRAINT company_company_name_key UNIQUE (company_name);
+DROP INDEX IF EXISTS jobboard_jobboard_name_key;
+ALTER TABLE jobboard ADD CONSTRAINT jobboard_jobboard_name_key UNIQUE (jobboard_name);
+
+ALTER TABLE application DROP COLUMN deleted_at;
+ALTER TABLE vacancy DROP COLUMN deleted_at;
+ALTER TABLE company DROP COLUMN deleted_at;
+ALTER TABLE jobboard DROP COLUMN deleted_at;
@@ -0,0 +1,16 @@
+ALTER TABLE jobboard ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE company ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE vacancy ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE application ADD COLUMN deleted_at TIMESTAMPTZ;
+
+-- Soft deleted jobboards and companies no longer hold on to their name.
+ALTER TABLE jobboard DROP CONSTRAINT jobboard_jobboard_name_key;
+CREATE UNIQUE INDEX jobboard_jobboard_name_key ON jobboard (jobboard_name) WHERE deleted_at IS NULL;
+ALTER TABLE company DROP CONSTRAINT company_company_name_key;
+CREATE UNIQUE INDEX company_company_name_key ON company (company_name) WHERE deleted_at IS NULL;
+
+-- Purging looks up the resources deleted before the retention period.
+CREATE INDEX jobboard_deleted_at_idx ON jobboard (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX company_deleted_at_idx ON company (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX vacancy_deleted_at_idx ON vacancy (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX application_deleted_at_idx ON application (deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,196 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use diesel::result::QueryResult;
+use diesel::{BoolExpressionMethods, Connection as _, ExpressionMethods, QueryDsl, RunQueryDsl};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+use crate::database::Connection;
+use crate::schema::jobboard_key;
+
+const KEY_ID_LENGTH: usize = 12;
+const SECRET_LENGTH: usize = 32;
+const SALT_LENGTH: usize = 16;
+
+/// API key handed out to a jobboard, formatted as `<key_id>.<secret>`.
+///
+/// Only the identifier is stored as is so the key can be looked up, the secret is stored as a salted SHA-256 hash.
+pub struct ApiKey {
+    key_id: String,
+    secret: String,
+}
+
+impl ApiKey {
+    pub fn generate() -> Self {
+        Self {
+            key_id: random_hex(KEY_ID_LENGTH),
+            secret: random_hex(SECRET_LENGTH),
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.split_once('.') {
+            Some((key_id, secret)) if !key_id.is_empty() && !secret.is_empty() => Some(Self {
+                key_id: key_id.to_string(),
+                secret: secret.to_string(),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// Hashes the secret with a freshly generated salt, formatted as `<salt>$<hash>`.
+    pub fn hash(&self) -> String {
+        let salt = random_hex(SALT_LENGTH);
+        let hash = digest(&salt, &self.secret);
+
+        format!("{}${}", salt, hash)
+    }
+
+    pub fn verify(&self, key_hash: &str) -> bool {
+        match key_hash.split_once('$') {
+            Some((salt, hash)) => constant_time_eq(digest(salt, &self.secret).as_bytes(), hash.as_bytes()),
+            None => false,
+        }
+    }
+}
+
+impl fmt::Display for ApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.key_id, self.secret)
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "jobboard_key"]
+struct NewJobboardKey<'a> {
+    key_id: &'a str,
+    jobboard_id: i64,
+    key_hash: String,
+}
+
+/// Issues a new API key to the jobboard.
+///
+/// Keys previously issued to the jobboard stay valid until `previous_keys_expire_at`, which gives callers a grace
+/// period to roll out the new key.
+pub fn issue(connection: &Connection, jobboard_id: i64, previous_keys_expire_at: DateTime<Utc>) -> QueryResult<ApiKey> {
+    connection.transaction(|| {
+        diesel::update(
+            jobboard_key::table
+                .filter(jobboard_key::jobboard_id.eq(jobboard_id))
+                .filter(
+                    jobboard_key::expires_at
+                        .is_null()
+                        .or(jobboard_key::expires_at.gt(previous_keys_expire_at)),
+                ),
+        )
+        .set(jobboard_key::expires_at.eq(previous_keys_expire_at))
+        .execute(connection)?;
+
+        let key = ApiKey::generate();
+
+        diesel::insert_into(jobboard_key::table)
+            .values(NewJobboardKey {
+                key_id: key.key_id(),
+                jobboard_id,
+                key_hash: key.hash(),
+            })
+            .execute(connection)?;
+
+        Ok(key)
+    })
+}
+
+fn random_hex(length: usize) -> String {
+    let mut bytes = vec![0; length];
+    OsRng.fill_bytes(&mut bytes);
+
+    hex::encode(bytes)
+}
+
+fn digest(salt: &str, secret: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(secret.as_bytes());
+
+    hex::encode(hasher.finalize())
+}
+
+pub(super) fn constant_time_eq(lhs: &[u8], rhs: &[u8]) -> bool {
+    lhs.len() == rhs.len() && lhs.iter().zip(rhs).fold(0, |acc, (l, r)| acc | (l ^ r)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_splits_the_key_id_from_the_secret() {
+        let key = ApiKey::parse("0123abcd.s3cr3t").unwrap();
+
+        assert_eq!(key.key_id(), "0123abcd");
+        assert_eq!(key.to_string(), "0123abcd.s3cr3t");
+    }
+
+    #[test]
+    fn parse_rejects_malformed_keys() {
+        for value in ["", "0123abcd", "0123abcd.", ".s3cr3t", "."] {
+            assert!(ApiKey::parse(value).is_none(), "{:?} should be rejected", value);
+        }
+    }
+
+    #[test]
+    fn parse_keeps_dots_of_the_secret() {
+        let key = ApiKey::parse("0123abcd.s3c.r3t").unwrap();
+
+        assert_eq!(key.key_id(), "0123abcd");
+        assert_eq!(key.to_string(), "0123abcd.s3c.r3t");
+    }
+
+    #[test]
+    fn verify_accepts_the_hash_of_the_key() {
+        let key = ApiKey::generate();
+        let key_hash = key.hash();
+
+        assert!(ApiKey::parse(&key.to_string()).unwrap().verify(&key_hash));
+    }
+
+    #[test]
+    fn hash_is_salted() {
+        let key = ApiKey::generate();
+
+        assert_ne!(key.hash(), key.hash());
+    }
+
+    #[test]
+    fn verify_rejects_other_secrets() {
+        let key = ApiKey::generate();
+        let key_hash = key.hash();
+        let forged = ApiKey::parse(&format!("{}.{}", key.key_id(), random_hex(SECRET_LENGTH))).unwrap();
+
+        assert!(!forged.verify(&key_hash));
+    }
+
+    #[test]
+    fn verify_rejects_malformed_hashes() {
+        let key = ApiKey::generate();
+        let key_hash = key.hash();
+        let (_, hash) = key_hash.split_once('$').unwrap();
+
+        assert!(!key.verify(hash));
+        assert!(!key.verify(""));
+    }
+
+    #[test]
+    fn constant_time_eq_compares_bytes() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secrets"));
+        assert!(constant_time_eq(b"", b""));
+    }
+}
@@ -0,0 +1,235 @@
+mod key;
+
+use diesel::dsl::now;
+use diesel::{BoolExpressionMethods, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+use rocket::outcome::Outcome;
+use rocket::request::{self, FromRequest, Request};
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::openapi3::{Object, SecurityRequirement, SecurityScheme, SecuritySchemeData};
+use rocket_okapi::request::{OpenApiFromRequest, RequestHeaderInput};
+
+pub use self::key::issue as issue_key;
+use self::key::ApiKey;
+use crate::error::reject;
+use crate::schema::{jobboard, jobboard_key};
+use crate::telemetry;
+use crate::{Config, Database, Error};
+
+const API_KEY_HEADER: &str = "X-Api-Key";
+const API_KEY_SECURITY_SCHEME: &str = "ApiKey";
+const ADMIN_KEY_HEADER: &str = "X-Admin-Key";
+const ADMIN_KEY_SECURITY_SCHEME: &str = "AdminKey";
+
+/// Jobboard authenticated from the API key sent along with the request.
+///
+/// The key is read from the `X-Api-Key` header or, failing that, from an `Authorization: Bearer <key>` header.
+/// Only unexpired keys of active and verified jobboards are let through.
+#[derive(Clone, Copy, Debug)]
+pub struct Tenant {
+    jobboard_id: i64,
+}
+
+impl Tenant {
+    pub fn jobboard_id(&self) -> i64 {
+        self.jobboard_id
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Tenant {
+    type Error = Error;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let key = match extract_api_key(request) {
+            Some(key) => match ApiKey::parse(key) {
+                Some(key) => key,
+                None => return reject(request, Error::Unauthorized("Malformed API key".to_string())),
+            },
+            None => return reject(request, Error::Unauthorized("Missing API key".to_string())),
+        };
+        let key_id = key.key_id().to_string();
+
+        let database = match request.guard::<Database>().await {
+            Outcome::Success(database) => database,
+            _ => return reject(request, Error::InternalError("Database is unreachable".to_string())),
+        };
+
+        let jobboard = database
+            .run(move |connection| {
+                jobboard_key::table
+                    .inner_join(jobboard::table)
+                    .filter(jobboard_key::key_id.eq(key_id))
+                    .filter(jobboard::deleted_at.is_null())
+                    .filter(jobboard_key::expires_at.is_null().or(jobboard_key::expires_at.gt(now)))
+                    .select((
+                        jobboard_key::key_hash,
+                        jobboard::jobboard_id,
+                        jobboard::verified,
+                        jobboard::active,
+                    ))
+                    .first::<(String, i64, bool, bool)>(connection)
+                    .optional()
+            })
+            .await;
+
+        match jobboard {
+            Ok(Some((key_hash, _, _, _))) if !key.verify(&key_hash) => {
+                reject(request, Error::Unauthorized("Invalid API key".to_string()))
+            }
+            Ok(Some((_, jobboard_id, true, true))) => {
+                telemetry::record_jobboard(request, jobboard_id);
+                Outcome::Success(Self { jobboard_id })
+            }
+            Ok(Some(_)) => reject(
+                request,
+                Error::Forbidden("Jobboard is either inactive or not verified".to_string()),
+            ),
+            Ok(None) => reject(request, Error::Unauthorized("Invalid API key".to_string())),
+            Err(e) => reject(request, e.into()),
+        }
+    }
+}
+
+impl<'r> OpenApiFromRequest<'r> for Tenant {
+    fn from_request_input(
+        _gen: &mut OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        Ok(security_input(
+            API_KEY_SECURITY_SCHEME,
+            API_KEY_HEADER,
+            "API key issued to the jobboard.",
+        ))
+    }
+}
+
+/// Platform administrator authenticated from the `X-Admin-Key` header.
+///
+/// The expected key is the `admin_key` of the [`Config`], administration being disabled when it is unset or blank.
+#[derive(Clone, Copy, Debug)]
+pub struct Admin {
+    _private: (),
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Admin {
+    type Error = Error;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let admin_key = match request.rocket().state::<Config>().and_then(Config::admin_key) {
+            Some(admin_key) => admin_key,
+            None => return reject(request, Error::Forbidden("Administration is disabled".to_string())),
+        };
+
+        match request
+            .headers()
+            .get_one(ADMIN_KEY_HEADER)
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+        {
+            Some(key) if key::constant_time_eq(key.as_bytes(), admin_key.as_bytes()) => {
+                Outcome::Success(Self { _private: () })
+            }
+            Some(_) => reject(request, Error::Unauthorized("Invalid admin key".to_string())),
+            None => reject(request, Error::Unauthorized("Missing admin key".to_string())),
+        }
+    }
+}
+
+impl<'r> OpenApiFromRequest<'r> for Admin {
+    fn from_request_input(
+        _gen: &mut OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        Ok(security_input(
+            ADMIN_KEY_SECURITY_SCHEME,
+            ADMIN_KEY_HEADER,
+            "Key of the platform administrator.",
+        ))
+    }
+}
+
+/// Either a platform administrator or a jobboard, for routes open to both roles.
+///
+/// Requests carrying an `X-Admin-Key` header are authenticated as [`Admin`], the others as [`Tenant`].
+#[derive(Clone, Copy, Debug)]
+pub enum Caller {
+    Admin(Admin),
+    Tenant(Tenant),
+}
+
+impl Caller {
+    /// Tells whether the caller is allowed to act on behalf of the given jobboard.
+    pub fn can_access(&self, jobboard_id: i64) -> bool {
+        match self {
+            Self::Admin(_) => true,
+            Self::Tenant(tenant) => tenant.jobboard_id() == jobboard_id,
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Caller {
+    type Error = Error;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        if request.headers().contains(ADMIN_KEY_HEADER) {
+            request.guard::<Admin>().await.map(Self::Admin)
+        } else {
+            request.guard::<Tenant>().await.map(Self::Tenant)
+        }
+    }
+}
+
+impl<'r> OpenApiFromRequest<'r> for Caller {
+    fn from_request_input(
+        _gen: &mut OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        Ok(security_input(
+            API_KEY_SECURITY_SCHEME,
+            API_KEY_HEADER,
+            "API key issued to the jobboard. Platform administrators may send their `X-Admin-Key` instead.",
+        ))
+    }
+}
+
+fn security_input(scheme_name: &str, header: &str, description: &str) -> RequestHeaderInput {
+    let scheme = SecurityScheme {
+        description: Some(description.to_string()),
+        data: SecuritySchemeData::ApiKey {
+            name: header.to_string(),
+            location: "header".to_string(),
+        },
+        extensions: Object::default(),
+    };
+
+    let mut requirement = SecurityRequirement::new();
+    requirement.insert(scheme_name.to_string(), Vec::new());
+
+    RequestHeaderInput::Security(scheme_name.to_string(), scheme, requirement)
+}
+
+/// Tells whether the request carries credentials of any kind, whether valid or not.
+pub(crate) fn has_credentials(request: &Request<'_>) -> bool {
+    let headers = request.headers();
+
+    headers.contains(API_KEY_HEADER) || headers.contains("Authorization") || headers.contains(ADMIN_KEY_HEADER)
+}
+
+fn extract_api_key<'r>(request: &'r Request<'_>) -> Option<&'r str> {
+    request
+        .headers()
+        .get_one(API_KEY_HEADER)
+        .or_else(|| {
+            request
+                .headers()
+                .get_one("Authorization")
+                .and_then(|value| value.strip_prefix("Bearer "))
+        })
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+}
@@ -0,0 +1,127 @@
+use chrono::{DateTime, Utc};
+use rocket::http::{Method, Status};
+use rocket::response::Builder;
+use rocket::Request;
+use sha2::{Digest, Sha256};
+
+use crate::auth;
+
+const HTTP_DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// Validators of a response, from which clients can tell whether the copy they cached is still fresh.
+pub struct Validators {
+    etag: String,
+    last_modified: Option<DateTime<Utc>>,
+}
+
+impl Validators {
+    /// The `ETag` is the version of the resource when known, a weak tag derived from the `body` otherwise.
+    pub fn new(version: Option<i32>, last_modified: Option<DateTime<Utc>>, body: &str) -> Self {
+        let etag = match version {
+            Some(version) => format!("\"{}\"", version),
+            None => format!("W/\"{}\"", hex::encode(&Sha256::digest(body.as_bytes())[..16])),
+        };
+
+        Self { etag, last_modified }
+    }
+
+    pub fn apply(&self, response: &mut Builder<'_>) {
+        response.raw_header("ETag", self.etag.clone());
+
+        if let Some(last_modified) = self.last_modified {
+            response.raw_header("Last-Modified", last_modified.format(HTTP_DATE_FORMAT).to_string());
+        }
+    }
+
+    /// Tells whether the copy of the client, as described by the conditional headers of the request, is still fresh.
+    pub fn is_fresh(&self, request: &Request<'_>) -> bool {
+        self.matches(
+            request.headers().get_one("If-None-Match"),
+            request.headers().get_one("If-Modified-Since"),
+        )
+    }
+
+    fn matches(&self, if_none_match: Option<&str>, if_modified_since: Option<&str>) -> bool {
+        // `If-Modified-Since` is to be ignored along with `If-None-Match` (RFC 7232, section 3.3).
+        if let Some(if_none_match) = if_none_match {
+            return if_none_match
+                .split(',')
+                .map(str::trim)
+                .any(|etag| etag == "*" || opaque_tag(etag) == opaque_tag(&self.etag));
+        }
+
+        match (if_modified_since, self.last_modified) {
+            (Some(if_modified_since), Some(last_modified)) => DateTime::parse_from_rfc2822(if_modified_since)
+                .is_ok_and(|if_modified_since| last_modified.timestamp() <= if_modified_since.timestamp()),
+            _ => false,
+        }
+    }
+}
+
+/// Tells whether the response to the request can be cached, which only successful reads can.
+pub fn is_cacheable(request: &Request<'_>, status: Status) -> bool {
+    matches!(request.method(), Method::Get | Method::Head) && status == Status::Ok
+}
+
+/// Responses are cached by clients provided they revalidate them, responses to authenticated requests being kept out of
+/// shared caches.
+pub fn cache_control(request: &Request<'_>) -> &'static str {
+    if auth::has_credentials(request) {
+        "private, no-cache"
+    } else {
+        "public, no-cache"
+    }
+}
+
+/// Weak comparison of entity tags, which ignores the weakness indicator (RFC 7232, section 2.3.2).
+fn opaque_tag(etag: &str) -> &str {
+    etag.strip_prefix("W/").unwrap_or(etag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validators() -> Validators {
+        let last_modified = DateTime::parse_from_rfc3339("2022-06-01T08:00:00Z").unwrap();
+
+        Validators::new(Some(3), Some(last_modified.with_timezone(&Utc)), "{}")
+    }
+
+    #[test]
+    fn etag_is_the_version_or_a_weak_hash_of_the_body() {
+        assert_eq!(validators().etag, "\"3\"");
+
+        let etag = Validators::new(None, None, "{}").etag;
+        assert!(etag.starts_with("W/\"") && etag.ends_with('"'));
+        assert_eq!(etag, Validators::new(None, None, "{}").etag);
+        assert_ne!(etag, Validators::new(None, None, "[]").etag);
+    }
+
+    #[test]
+    fn fresh_when_an_etag_matches() {
+        assert!(validators().matches(Some("\"3\""), None));
+        assert!(validators().matches(Some("\"2\", W/\"3\""), None));
+        assert!(validators().matches(Some("*"), None));
+        assert!(!validators().matches(Some("\"2\""), None));
+    }
+
+    #[test]
+    fn fresh_when_unmodified_since() {
+        assert!(validators().matches(None, Some("Wed, 01 Jun 2022 08:00:00 GMT")));
+        assert!(validators().matches(None, Some("Thu, 02 Jun 2022 08:00:00 GMT")));
+        assert!(!validators().matches(None, Some("Wed, 01 Jun 2022 07:59:59 GMT")));
+        assert!(!validators().matches(None, Some("yesterday")));
+    }
+
+    #[test]
+    fn if_none_match_takes_precedence_over_if_modified_since() {
+        assert!(!validators().matches(Some("\"2\""), Some("Thu, 02 Jun 2022 08:00:00 GMT")));
+    }
+
+    #[test]
+    fn stale_without_conditional_headers() {
+        assert!(!validators().matches(None, None));
+        assert!(!Validators::new(Some(3), None, "{}").matches(None, Some("Wed, 01 Jun 2022 08:00:00 GMT")));
+    }
+}
@@ -1,12 +1,29 @@
 use rocket::{catch, Request};
 
+use crate::error::rejection;
 use crate::{Error, Response};
 
 #[catch(400)]
-pub fn bad_request() -> Response<()> {
-    Response::Failure(Error::BadRequest(
-        "Request is malformed: expected valid JSON".to_string(),
-    ))
+pub fn bad_request(request: &Request) -> Response<()> {
+    Response::Failure(
+        rejection(request)
+            .unwrap_or_else(|| Error::BadRequest("Request is malformed: expected valid JSON".to_string())),
+    )
+}
+
+#[catch(401)]
+pub fn unauthorized(request: &Request) -> Response<()> {
+    Response::Failure(
+        rejection(request)
+            .unwrap_or_else(|| Error::Unauthorized("A valid API key is required to access this resource".to_string())),
+    )
+}
+
+#[catch(403)]
+pub fn forbidden(request: &Request) -> Response<()> {
+    Response::Failure(
+        rejection(request).unwrap_or_else(|| Error::Forbidden("Access to this resource is not allowed".to_string())),
+    )
 }
 
 #[catch(404)]
@@ -15,8 +32,105 @@ pub fn not_found(request: &Request) -> Response<()> {
 }
 
 #[catch(422)]
-pub fn unprocessable_entity() -> Response<()> {
-    Response::Failure(Error::InvalidData(
-        "JSON is well-formed but contains semantic errors".to_string(),
-    ))
+pub fn unprocessable_entity(request: &Request) -> Response<()> {
+    Response::Failure(
+        rejection(request)
+            .unwrap_or_else(|| Error::InvalidData("JSON is well-formed but contains semantic errors".to_string())),
+    )
+}
+
+#[catch(428)]
+pub fn precondition_required(request: &Request) -> Response<()> {
+    Response::Failure(rejection(request).unwrap_or_else(|| {
+        Error::PreconditionRequired("An If-Match header is required to change this resource".to_string())
+    }))
+}
+
+#[catch(500)]
+pub fn internal_error(request: &Request) -> Response<()> {
+    Response::Failure(
+        rejection(request).unwrap_or_else(|| Error::InternalError("Request failed unexpectedly".to_string())),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::http::{Header, Status};
+    use rocket::local::blocking::{Client, LocalResponse};
+    use rocket::{catchers, get, put, routes, uri};
+    use serde_json::Value;
+
+    use super::*;
+    use crate::{Admin, IfMatch};
+
+    #[put("/")]
+    fn guarded(_if_match: IfMatch) -> &'static str {
+        "guarded"
+    }
+
+    #[get("/admin")]
+    fn administered(_admin: Admin) -> &'static str {
+        "administered"
+    }
+
+    fn client() -> Client {
+        let rocket = rocket::build().mount("/", routes![guarded, administered]).register(
+            "/",
+            catchers![bad_request, unauthorized, forbidden, precondition_required],
+        );
+
+        Client::untracked(rocket).unwrap()
+    }
+
+    fn problem(response: LocalResponse<'_>) -> (Status, String) {
+        let status = response.status();
+        let problem: Value = response.into_json().unwrap();
+
+        (status, problem["detail"].as_str().unwrap().to_string())
+    }
+
+    #[test]
+    fn bad_request_reports_invalid_if_match_headers() {
+        let client = client();
+
+        for if_match in ["W/\"3\"", "3"] {
+            let (status, detail) = problem(
+                client
+                    .put(uri!(guarded))
+                    .header(Header::new("If-Match", if_match))
+                    .dispatch(),
+            );
+
+            assert_eq!(status, Status::BadRequest);
+            assert_eq!(
+                detail,
+                format!(
+                    "Invalid If-Match header '{}': expected a strong ETag such as \"3\"",
+                    if_match
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn precondition_required_reports_missing_if_match_headers() {
+        let (status, detail) = problem(client().put(uri!(guarded)).dispatch());
+
+        assert_eq!(status, Status::PreconditionRequired);
+        assert_eq!(detail, "If-Match header is required to change this resource");
+    }
+
+    #[test]
+    fn forbidden_reports_the_guard_reason() {
+        let client = client();
+        let (status, detail) = problem(
+            client
+                .get(uri!(administered))
+                .header(Header::new("X-Admin-Key", "secret"))
+                .dispatch(),
+        );
+
+        assert_eq!(status, Status::Forbidden);
+        assert_eq!(detail, "Administration is disabled");
+    }
 }
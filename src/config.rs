@@ -0,0 +1,28 @@
+use serde::Deserialize;
+
+use crate::telemetry::LogFormat;
+
+/// Platform settings, extracted from the Rocket configuration (`Rocket.toml` or `ROCKET_*` environment variables).
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    /// Key granting the platform administrator role. Administration routes are unreachable when unset or blank.
+    #[serde(default)]
+    pub admin_key: Option<String>,
+    /// Number of seconds between two runs of the scheduler publishing and expiring vacancies.
+    #[serde(default = "default_scheduler_interval")]
+    pub scheduler_interval: u64,
+    /// Output of the logs, either `pretty` or `json`.
+    #[serde(default)]
+    pub log_format: LogFormat,
+}
+
+impl Config {
+    /// Key of the platform administrator, if administration is enabled.
+    pub fn admin_key(&self) -> Option<&str> {
+        self.admin_key.as_deref().filter(|key| !key.trim().is_empty())
+    }
+}
+
+fn default_scheduler_interval() -> u64 {
+    60
+}
@@ -1,19 +1,64 @@
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+use std::time::Instant;
+
 use diesel::associations::HasTable;
-use diesel::query_builder::{AsChangeset, DeleteStatement, InsertStatement, IntoUpdateTarget, UpdateStatement};
-use diesel::query_dsl::methods::{ExecuteDsl, FindDsl};
+use diesel::query_builder::{AsChangeset, InsertStatement, IntoUpdateTarget, UpdateStatement};
+use diesel::query_dsl::methods::FindDsl;
 use diesel::query_dsl::LoadQuery;
 use diesel::result::Error;
-use diesel::{Insertable, RunQueryDsl};
+use diesel::{Connection as _, Insertable, RunQueryDsl};
+use rocket::fairing::Fairing;
+use rocket::request::{self, FromRequest, Request};
 use rocket_okapi::request::OpenApiFromRequest;
 use rocket_sync_db_pools::{database, diesel::PgConnection};
 
-type Connection = PgConnection;
+use crate::telemetry;
+use crate::version::{check_bumped_version, Versioned};
+
+pub(crate) type Connection = PgConnection;
 
-#[derive(OpenApiFromRequest)]
 #[database("main")]
-pub struct Database(Connection);
+struct Pool(Connection);
+
+/// Connection pool of the main database, which accounts for the time requests spend waiting for it.
+#[derive(OpenApiFromRequest)]
+pub struct Database {
+    pool: Pool,
+    database_time: Arc<AtomicU64>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Database {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        request.guard::<Pool>().await.map(|pool| Self {
+            pool,
+            database_time: telemetry::database_time(request),
+        })
+    }
+}
 
 impl Database {
+    pub fn fairing() -> impl Fairing {
+        Pool::fairing()
+    }
+
+    /// Runs `f` with a connection of the pool.
+    pub async fn run<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut Connection) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let start = Instant::now();
+        let result = self.pool.run(f).await;
+
+        telemetry::add_database_time(&self.database_time, start.elapsed());
+
+        result
+    }
+
     pub async fn get<T, I, R>(&self, table: T, id: I) -> Result<R, Error>
     where
         T: FindDsl<I> + Send + 'static,
@@ -33,14 +78,6 @@ impl Database {
         .await
     }
 
-    pub async fn get_all<T, R>(&self, table: T) -> Result<Vec<R>, Error>
-    where
-        T: RunQueryDsl<Connection> + LoadQuery<Connection, R> + Send + 'static,
-        R: Send + 'static,
-    {
-        self.run(|connection| table.load(connection)).await
-    }
-
     pub async fn create<T, U, R>(&self, table: T, new_resource: U) -> Result<R, Error>
     where
         U: Insertable<T> + Send + 'static,
@@ -52,40 +89,69 @@ impl Database {
             .await
     }
 
-    pub async fn update<T, I, U, R>(&self, table: T, id: I, resource_changeset: U) -> Result<R, Error>
+    /// Updates the resource provided it is still at the given `version`, failing with
+    /// [`crate::Error::PreconditionFailed`] otherwise.
+    pub async fn update<T, I, U, R>(
+        &self,
+        table: T,
+        id: I,
+        version: i32,
+        resource_changeset: U,
+    ) -> Result<R, crate::Error>
     where
         T: FindDsl<I> + Send + 'static,
         T::Output: IntoUpdateTarget + HasTable,
         U: AsChangeset<Target = <T::Output as HasTable>::Table> + Send + 'static,
         I: Send + 'static,
-        R: Send + 'static,
+        R: Versioned + Send + 'static,
         UpdateStatement<<T::Output as HasTable>::Table, <T::Output as IntoUpdateTarget>::WhereClause, U::Changeset>:
             LoadQuery<Connection, R>,
     {
-        self.run(|connection| {
-            diesel::update(table.find(id))
-                .set(resource_changeset)
-                .get_result(connection)
+        self.run(move |connection| {
+            connection.transaction(|| {
+                let resource: R = diesel::update(table.find(id))
+                    .set(resource_changeset)
+                    .get_result(connection)?;
+
+                check_bumped_version(version, resource.version())?;
+
+                Ok(resource)
+            })
         })
         .await
     }
 
-    pub async fn delete<T, I>(&self, table: T, id: I) -> Result<(), Error>
+    /// Soft deletes the resource provided it is still at the given `version`, failing with
+    /// [`crate::Error::PreconditionFailed`] otherwise. The `deletion` changeset sets the `deleted_at` column of the
+    /// resource `R`.
+    pub async fn delete<T, I, U, R>(&self, table: T, id: I, version: i32, deletion: U) -> Result<(), crate::Error>
     where
         T: FindDsl<I> + Send + 'static,
         T::Output: IntoUpdateTarget + HasTable,
+        U: AsChangeset<Target = <T::Output as HasTable>::Table> + Send + 'static,
         I: Send + 'static,
-        DeleteStatement<<T::Output as HasTable>::Table, <T::Output as IntoUpdateTarget>::WhereClause>:
-            ExecuteDsl<Connection>,
+        R: Versioned + Send + 'static,
+        UpdateStatement<<T::Output as HasTable>::Table, <T::Output as IntoUpdateTarget>::WhereClause, U::Changeset>:
+            LoadQuery<Connection, R>,
     {
-        self.run(move |connection| {
-            diesel::delete(table.find(id))
-                .execute(connection)
-                .and_then(|count| match count {
-                    0 => Err(Error::NotFound),
-                    _ => Ok(()),
-                })
-        })
-        .await
+        self.update::<T, I, U, R>(table, id, version, deletion)
+            .await
+            .map(|_| ())
+    }
+
+    /// Restores a soft deleted resource whatever its version, the `restoration` changeset clearing its `deleted_at`
+    /// column.
+    pub async fn restore<T, I, U, R>(&self, table: T, id: I, restoration: U) -> Result<R, Error>
+    where
+        T: FindDsl<I> + Send + 'static,
+        T::Output: IntoUpdateTarget + HasTable,
+        U: AsChangeset<Target = <T::Output as HasTable>::Table> + Send + 'static,
+        I: Send + 'static,
+        R: Send + 'static,
+        UpdateStatement<<T::Output as HasTable>::Table, <T::Output as IntoUpdateTarget>::WhereClause, U::Changeset>:
+            LoadQuery<Connection, R>,
+    {
+        self.run(move |connection| diesel::update(table.find(id)).set(restoration).get_result(connection))
+            .await
     }
 }
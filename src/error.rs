@@ -1,16 +1,32 @@
 use diesel::result::{DatabaseErrorKind, Error as DieselError};
 use rocket::http::Status;
+use rocket::outcome::Outcome;
 use rocket::serde::json::Error as JsonError;
+use rocket::Request;
 use thiserror::Error;
 
-#[derive(Debug, Error)]
+use crate::validation::FieldError;
+
+#[derive(Clone, Debug, Error)]
 pub enum Error {
     #[error("{0}")]
     BadRequest(String),
     #[error("Conflicted data : {0}")]
     ConflictedData(String),
+    #[error("Conflicted data : {} conflicting field(s)", .0.len())]
+    ConflictedFields(Vec<FieldError>),
     #[error("Invalid data: {0}")]
     InvalidData(String),
+    #[error("Invalid data: {} invalid field(s)", .0.len())]
+    InvalidFields(Vec<FieldError>),
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+    #[error("Precondition failed: {0}")]
+    PreconditionFailed(String),
+    #[error("Precondition required: {0}")]
+    PreconditionRequired(String),
     #[error("Resource not found")]
     NotFound,
     #[error("Unknown route: {0}")]
@@ -20,35 +36,305 @@ pub enum Error {
 }
 
 impl Error {
+    /// Stable, machine-readable code of the error, which identifies its problem type.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::BadRequest(_) => "bad_request",
+            Self::ConflictedData(_) => "conflicted_data",
+            Self::ConflictedFields(_) => "conflicted_fields",
+            Self::InvalidData(_) => "invalid_data",
+            Self::InvalidFields(_) => "invalid_fields",
+            Self::Unauthorized(_) => "unauthorized",
+            Self::Forbidden(_) => "forbidden",
+            Self::PreconditionFailed(_) => "precondition_failed",
+            Self::PreconditionRequired(_) => "precondition_required",
+            Self::NotFound => "not_found",
+            Self::UnknownRoute(_) => "unknown_route",
+            Self::InternalError(_) => "internal_error",
+        }
+    }
+
+    /// Short summary of the problem, which is the same for every error of a given code.
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::BadRequest(_) => "Bad request",
+            Self::ConflictedData(_) | Self::ConflictedFields(_) => "Conflicted data",
+            Self::InvalidData(_) | Self::InvalidFields(_) => "Invalid data",
+            Self::Unauthorized(_) => "Unauthorized",
+            Self::Forbidden(_) => "Forbidden",
+            Self::PreconditionFailed(_) => "Precondition failed",
+            Self::PreconditionRequired(_) => "Precondition required",
+            Self::NotFound => "Resource not found",
+            Self::UnknownRoute(_) => "Unknown route",
+            Self::InternalError(_) => "Internal error",
+        }
+    }
+
+    /// Explanation specific to this occurrence of the problem.
+    pub fn detail(&self) -> String {
+        match self {
+            Self::BadRequest(detail)
+            | Self::ConflictedData(detail)
+            | Self::InvalidData(detail)
+            | Self::Unauthorized(detail)
+            | Self::Forbidden(detail)
+            | Self::PreconditionFailed(detail)
+            | Self::PreconditionRequired(detail) => detail.clone(),
+            // Internal details are logged rather than disclosed.
+            Self::InternalError(_) => {
+                "An unexpected error occurred, please report it along with the request id".to_string()
+            }
+            Self::InvalidFields(errors) => format!("{} invalid field(s)", errors.len()),
+            Self::ConflictedFields(errors) => errors.iter().map(FieldError::message).collect::<Vec<_>>().join(", "),
+            Self::NotFound => "Resource not found".to_string(),
+            Self::UnknownRoute(uri) => format!("No route matches '{}'", uri),
+        }
+    }
+
     pub fn get_http_status(&self) -> Status {
         match *self {
-            Self::InvalidData(_) => Status::UnprocessableEntity,
-            Self::ConflictedData(_) => Status::Conflict,
+            Self::InvalidData(_) | Self::InvalidFields(_) => Status::UnprocessableEntity,
+            Self::ConflictedData(_) | Self::ConflictedFields(_) => Status::Conflict,
             Self::BadRequest(_) => Status::BadRequest,
+            Self::Unauthorized(_) => Status::Unauthorized,
+            Self::Forbidden(_) => Status::Forbidden,
+            Self::PreconditionFailed(_) => Status::PreconditionFailed,
+            Self::PreconditionRequired(_) => Status::PreconditionRequired,
             Self::NotFound | Self::UnknownRoute(_) => Status::NotFound,
             _ => Status::InternalServerError,
         }
     }
 }
 
+/// Error a guard of the request failed with, if one has.
+struct Rejection(Option<Error>);
+
+/// Fails a guard of the request with the `error`, which the catcher of its status then reports.
+pub(crate) fn reject<S, F>(request: &Request<'_>, error: Error) -> Outcome<S, (Status, Error), F> {
+    request.local_cache(|| Rejection(Some(error.clone())));
+
+    Outcome::Failure((error.get_http_status(), error))
+}
+
+/// Tells why a guard of the request failed, if one has.
+pub(crate) fn rejection(request: &Request<'_>) -> Option<Error> {
+    request.local_cache(|| Rejection(None)).0.clone()
+}
+
 impl From<JsonError<'_>> for Error {
     fn from(e: JsonError<'_>) -> Self {
         match e {
             JsonError::Io(e) => Self::BadRequest(format!("Cannot process JSON: {}", e)),
+            // Well-formed JSON which does not match the expected body, such as missing or unknown fields.
+            JsonError::Parse(_, e) if e.is_data() => Self::InvalidData(e.to_string()),
             JsonError::Parse(data, e) => Self::BadRequest(format!("Invalid JSON format '{}': {}", data, e)),
         }
     }
 }
 
+/// Constraint of the schema, reported as an error on the field it constrains rather than with the database wording.
+struct KnownConstraint {
+    name: &'static str,
+    field: &'static str,
+    code: &'static str,
+    message: &'static str,
+}
+
+const KNOWN_CONSTRAINTS: &[KnownConstraint] = &[
+    KnownConstraint {
+        name: "jobboard_jobboard_name_key",
+        field: "jobboard_name",
+        code: "taken",
+        message: "Jobboard name is already taken",
+    },
+    KnownConstraint {
+        name: "company_company_name_key",
+        field: "company_name",
+        code: "taken",
+        message: "Company name is already taken",
+    },
+    KnownConstraint {
+        name: "company_jobboard_id_fkey",
+        field: "jobboard_id",
+        code: "unknown_reference",
+        message: "Must refer to an existing jobboard",
+    },
+    KnownConstraint {
+        name: "vacancy_jobboard_id_fkey",
+        field: "jobboard_id",
+        code: "unknown_reference",
+        message: "Must refer to an existing jobboard",
+    },
+    KnownConstraint {
+        name: "vacancy_company_id_fkey",
+        field: "company_id",
+        code: "unknown_reference",
+        message: "Must refer to an existing company",
+    },
+    KnownConstraint {
+        name: "application_jobboard_id_fkey",
+        field: "jobboard_id",
+        code: "unknown_reference",
+        message: "Must refer to an existing jobboard",
+    },
+    KnownConstraint {
+        name: "application_vacancy_id_fkey",
+        field: "vacancy_id",
+        code: "unknown_reference",
+        message: "Must refer to an existing vacancy",
+    },
+    KnownConstraint {
+        name: "vacancy_commission_check",
+        field: "commission",
+        code: "too_large",
+        message: "Must be at most 10000",
+    },
+    KnownConstraint {
+        name: "vacancy_schedule_check",
+        field: "expires_at",
+        code: "before_publish_at",
+        message: "Must be after publish_at",
+    },
+    KnownConstraint {
+        name: "vacancy_status_check",
+        field: "status",
+        code: "unknown_status",
+        message: "Must be a known vacancy status",
+    },
+    KnownConstraint {
+        name: "application_status_check",
+        field: "status",
+        code: "unknown_status",
+        message: "Must be a known application status",
+    },
+];
+
+impl KnownConstraint {
+    fn find(name: Option<&str>) -> Option<&'static Self> {
+        name.and_then(|name| KNOWN_CONSTRAINTS.iter().find(|constraint| constraint.name == name))
+    }
+
+    fn field_error(&self) -> FieldError {
+        FieldError::new(self.field, self.code, self.message)
+    }
+}
+
 impl From<DieselError> for Error {
     fn from(e: DieselError) -> Self {
         match e {
             DieselError::NotFound => Self::NotFound,
-            DieselError::DatabaseError(
-                DatabaseErrorKind::UniqueViolation | DatabaseErrorKind::ForeignKeyViolation,
-                e,
-            ) => Self::ConflictedData(e.message().to_string()),
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, info) => {
+                match KnownConstraint::find(info.constraint_name()) {
+                    Some(constraint) => Self::ConflictedFields(vec![constraint.field_error()]),
+                    None => Self::ConflictedData("Resource conflicts with an existing one".to_string()),
+                }
+            }
+            DieselError::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, info) => {
+                match KnownConstraint::find(info.constraint_name()) {
+                    Some(constraint) => Self::InvalidFields(vec![constraint.field_error()]),
+                    None => Self::ConflictedData(
+                        "Resource either refers to a missing resource or is still referred to".to_string(),
+                    ),
+                }
+            }
+            // Diesel 1.4 has no dedicated kind for check and not null violations, which are told apart by the
+            // constraint or the column they are about.
+            DieselError::DatabaseError(_, info) => {
+                match (
+                    KnownConstraint::find(info.constraint_name()),
+                    info.constraint_name(),
+                    info.column_name(),
+                ) {
+                    (Some(constraint), ..) => Self::InvalidFields(vec![constraint.field_error()]),
+                    (None, Some(_), _) => Self::InvalidData("Resource breaks a data constraint".to_string()),
+                    (None, None, Some(column)) => {
+                        Self::InvalidFields(vec![FieldError::new(column, "required", "Must not be null")])
+                    }
+                    (None, None, None) => Self::InternalError(info.message().to_string()),
+                }
+            }
+            // Raised by updates without any change, which validation normally rejects beforehand.
+            DieselError::QueryBuilderError(e) => Self::BadRequest(e.to_string()),
             e => Self::InternalError(e.to_string()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use diesel::result::DatabaseErrorInformation;
+    use serde_json::json;
+
+    use super::*;
+
+    struct Violation {
+        constraint_name: &'static str,
+    }
+
+    impl DatabaseErrorInformation for Violation {
+        fn message(&self) -> &str {
+            "violates a constraint"
+        }
+
+        fn details(&self) -> Option<&str> {
+            None
+        }
+
+        fn hint(&self) -> Option<&str> {
+            None
+        }
+
+        fn table_name(&self) -> Option<&str> {
+            None
+        }
+
+        fn column_name(&self) -> Option<&str> {
+            None
+        }
+
+        fn constraint_name(&self) -> Option<&str> {
+            Some(self.constraint_name)
+        }
+    }
+
+    fn violation(kind: DatabaseErrorKind, constraint_name: &'static str) -> Error {
+        DieselError::DatabaseError(kind, Box::new(Violation { constraint_name })).into()
+    }
+
+    #[test]
+    fn known_unique_violations_are_reported_on_their_field() {
+        match violation(DatabaseErrorKind::UniqueViolation, "company_company_name_key") {
+            Error::ConflictedFields(errors) => assert_eq!(
+                serde_json::to_value(errors).unwrap(),
+                json!([{ "field": "company_name", "code": "taken", "message": "Company name is already taken" }])
+            ),
+            error => panic!("unexpected error {:?}", error),
+        }
+    }
+
+    #[test]
+    fn known_foreign_key_violations_are_reported_on_their_field() {
+        match violation(DatabaseErrorKind::ForeignKeyViolation, "application_vacancy_id_fkey") {
+            Error::InvalidFields(errors) => assert_eq!(
+                serde_json::to_value(errors).unwrap(),
+                json!([{
+                    "field": "vacancy_id",
+                    "code": "unknown_reference",
+                    "message": "Must refer to an existing vacancy"
+                }])
+            ),
+            error => panic!("unexpected error {:?}", error),
+        }
+    }
+
+    #[test]
+    fn unknown_constraints_keep_the_database_wording_out() {
+        let error = violation(DatabaseErrorKind::UniqueViolation, "unknown_key");
+        assert!(
+            matches!(error, Error::ConflictedData(message) if message == "Resource conflicts with an existing one")
+        );
+
+        let error = violation(DatabaseErrorKind::ForeignKeyViolation, "unknown_fkey");
+        assert!(matches!(error, Error::ConflictedData(message) if !message.contains("violates")));
+    }
+}
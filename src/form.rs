@@ -0,0 +1,19 @@
+use chrono::{DateTime, Utc};
+use rocket::form::{self, FromFormField, ValueField};
+use schemars::JsonSchema;
+
+/// Date and time query parameter, formatted as RFC 3339 (e.g. `2022-06-01T08:00:00Z`).
+///
+/// Offsets such as `+02:00` must be percent-encoded, a literal `+` being decoded as a space in query strings.
+#[derive(Clone, Copy, Debug, JsonSchema)]
+#[schemars(transparent)]
+pub struct Timestamp(pub DateTime<Utc>);
+
+impl<'v> FromFormField<'v> for Timestamp {
+    fn from_value(field: ValueField<'v>) -> form::Result<'v, Self> {
+        let timestamp =
+            DateTime::parse_from_rfc3339(field.value).map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+        Ok(Self(timestamp.with_timezone(&Utc)))
+    }
+}
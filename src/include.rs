@@ -0,0 +1,74 @@
+use crate::Error;
+
+/// Related resources to embed in a response, as requested by the `include` query parameter, a comma-separated list
+/// of relation names (e.g. `company,jobboard`).
+#[derive(Clone, Debug, Default)]
+pub struct Include {
+    relations: Vec<&'static str>,
+}
+
+impl Include {
+    /// Parses the `include` query parameter, only the given `relations` being available.
+    pub fn parse(include: Option<&str>, relations: &[&'static str]) -> Result<Self, Error> {
+        let mut included = Vec::new();
+
+        for name in include
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            match relations.iter().find(|relation| **relation == name) {
+                Some(relation) if !included.contains(relation) => included.push(*relation),
+                Some(_) => {}
+                None => {
+                    return Err(Error::BadRequest(format!(
+                        "Cannot include '{}', expected one of: {}",
+                        name,
+                        relations.join(", ")
+                    )))
+                }
+            }
+        }
+
+        Ok(Self { relations: included })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.relations.is_empty()
+    }
+
+    pub fn contains(&self, relation: &str) -> bool {
+        self.relations.contains(&relation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RELATIONS: &[&str] = &["company", "jobboard"];
+
+    #[test]
+    fn parse_keeps_requested_relations() {
+        let include = Include::parse(Some("company, jobboard,company"), RELATIONS).unwrap();
+
+        assert_eq!(include.relations, vec!["company", "jobboard"]);
+        assert!(include.contains("company"));
+        assert!(!include.is_empty());
+    }
+
+    #[test]
+    fn parse_ignores_empty_names() {
+        for include in [None, Some(""), Some(" ,")] {
+            assert!(Include::parse(include, RELATIONS).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn parse_rejects_unknown_relations() {
+        let result = Include::parse(Some("company,vacancy"), RELATIONS);
+
+        assert!(matches!(result, Err(Error::BadRequest(message)) if message.contains("'vacancy'")));
+    }
+}
@@ -3,13 +3,29 @@
 #[macro_use]
 extern crate diesel;
 
+mod auth;
+mod cache;
 pub mod catchers;
+mod config;
 mod database;
 mod error;
+mod form;
+mod include;
+mod pagination;
+mod partial;
+pub mod request_id;
 mod response;
 pub mod routes;
+pub mod scheduler;
 mod schema;
+pub mod telemetry;
+mod validation;
+mod version;
 
+pub use auth::{Admin, Caller, Tenant};
+pub use config::Config;
 pub use database::Database;
 pub use error::Error;
 pub use response::Response;
+pub use validation::Validated;
+pub use version::IfMatch;
@@ -1,38 +1,69 @@
 use eyre::Report;
-use oh_platform::{catchers, routes, Database};
+use oh_platform::{catchers, request_id, routes, scheduler, telemetry, Config, Database};
 use rocket::catchers;
+use rocket::fairing::AdHoc;
 use rocket_okapi::swagger_ui::{self as swagger, SwaggerUIConfig};
 
 #[rocket::main]
 async fn main() -> Result<(), Report> {
     color_eyre::install()?;
 
+    let config: Config = rocket::Config::figment().extract()?;
+    telemetry::init(config.log_format)?;
+
     rocket::build()
+        .attach(AdHoc::config::<Config>())
+        .attach(request_id::fairing())
+        .attach(telemetry::fairing())
         .attach(Database::fairing())
+        .attach(scheduler::fairing())
         .mount(
             "/v1/",
-            rocket_okapi::openapi_get_routes![
+            telemetry::traced(rocket_okapi::openapi_get_routes![
                 routes::get_all_jobboards,
                 routes::add_new_jobboard,
                 routes::get_jobboard,
                 routes::update_jobboard,
+                routes::patch_jobboard,
                 routes::delete_jobboard,
+                routes::restore_jobboard,
+                routes::rotate_jobboard_key,
                 routes::get_all_companies,
                 routes::add_new_company,
                 routes::get_company,
                 routes::update_company,
+                routes::patch_company,
                 routes::delete_company,
+                routes::restore_company,
+                routes::get_jobboard_companies,
+                routes::add_new_jobboard_company,
                 routes::get_all_vacancies,
+                routes::search_vacancies,
                 routes::add_new_vacancy,
                 routes::get_vacancy,
                 routes::update_vacancy,
+                routes::patch_vacancy,
+                routes::publish_vacancy,
+                routes::pause_vacancy,
+                routes::fill_vacancy,
+                routes::close_vacancy,
                 routes::delete_vacancy,
+                routes::restore_vacancy,
+                routes::get_company_vacancies,
+                routes::add_new_company_vacancy,
+                routes::get_public_vacancies,
                 routes::get_all_applications,
                 routes::add_new_application,
                 routes::get_application,
                 routes::update_application,
-                routes::delete_application
-            ],
+                routes::patch_application,
+                routes::get_application_history,
+                routes::delete_application,
+                routes::restore_application,
+                routes::get_vacancy_applications,
+                routes::add_new_vacancy_application,
+                routes::purge_deleted
+            ]),
         )
         .mount(
             "/swagger/",
@@ -46,7 +77,11 @@ async fn main() -> Result<(), Report> {
             catchers![
                 catchers::not_found,
                 catchers::bad_request,
+                catchers::unauthorized,
+                catchers::forbidden,
                 catchers::unprocessable_entity,
+                catchers::precondition_required,
+                catchers::internal_error,
             ],
         )
         .launch()
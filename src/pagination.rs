@@ -0,0 +1,348 @@
+use diesel::deserialize::Queryable;
+use diesel::pg::Pg;
+use diesel::query_builder::{AsQuery, AstPass, Query, QueryFragment, QueryId};
+use diesel::result::QueryResult;
+use diesel::sql_types::{BigInt, HasSqlType};
+use diesel::{Column, RunQueryDsl};
+use rocket::FromForm;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::database::Connection;
+use crate::form::Timestamp;
+use crate::Error;
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 500;
+
+/// Slice of a listing requested through the `limit`, `offset`, `after` and `sort` query parameters.
+///
+/// Listings are ordered by identifier unless sorted otherwise, and `after` is a cursor on that identifier (keyset
+/// pagination) which, unlike `offset`, stays stable while resources are being created or deleted. The cursor is
+/// therefore only available on listings ordered by identifier. The limit is capped to 500 resources.
+#[derive(Clone, Debug)]
+pub struct Pagination {
+    limit: i64,
+    offset: i64,
+    after: Option<i64>,
+    sort: Vec<(&'static str, Direction)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Ascending,
+    Descending,
+}
+
+impl Pagination {
+    pub fn new(limit: Option<i64>, offset: Option<i64>, after: Option<i64>) -> Self {
+        Self {
+            limit: limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+            offset: offset.unwrap_or(0).max(0),
+            after,
+            sort: Vec::new(),
+        }
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.limit
+    }
+
+    pub fn offset(&self) -> i64 {
+        self.offset
+    }
+
+    /// Sorts the listing as requested by the `sort` query parameter, a comma-separated list of columns which are
+    /// sorted in descending order when prefixed with `-` (e.g. `-start_date,job_title`).
+    ///
+    /// Only the given `columns` can be sorted by.
+    pub fn sorted_by(mut self, sort: Option<&str>, columns: &[&'static str]) -> Result<Self, Error> {
+        for field in sort
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+        {
+            let (name, direction) = match field.strip_prefix('-') {
+                Some(name) => (name, Direction::Descending),
+                None => (field.strip_prefix('+').unwrap_or(field), Direction::Ascending),
+            };
+
+            match columns.iter().find(|column| **column == name) {
+                Some(column) => self.sort.push((*column, direction)),
+                None => {
+                    return Err(Error::BadRequest(format!(
+                        "Cannot sort by '{}', expected one of: {}",
+                        name,
+                        columns.join(", ")
+                    )))
+                }
+            }
+        }
+
+        if self.after.is_some() && !self.sort.is_empty() {
+            return Err(Error::BadRequest(
+                "Cursor pagination is only available on listings ordered by identifier".to_string(),
+            ));
+        }
+
+        Ok(self)
+    }
+}
+
+/// Query parameters shared by the listings, sliced and sorted as described by [`Pagination`].
+#[derive(FromForm, JsonSchema)]
+pub struct ListQuery {
+    limit: Option<i64>,
+    offset: Option<i64>,
+    /// Identifier of the last resource of the previous page, on listings ordered by identifier.
+    after: Option<i64>,
+    /// Comma-separated list of columns to sort by, in descending order when prefixed with `-`.
+    sort: Option<String>,
+    /// Only resources updated at or after this time, for incremental sync.
+    updated_since: Option<Timestamp>,
+}
+
+impl ListQuery {
+    /// Pagination of the listing, which can only be sorted by the given `columns`.
+    pub fn pagination(&self, columns: &[&'static str]) -> Result<Pagination, Error> {
+        Pagination::new(self.limit, self.offset, self.after).sorted_by(self.sort.as_deref(), columns)
+    }
+
+    pub fn updated_since(&self) -> Option<Timestamp> {
+        self.updated_since
+    }
+}
+
+/// Page of resources loaded from a [`Paginated`] query.
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub metadata: PageMetadata,
+}
+
+impl<T> Page<T> {
+    /// Converts the resources of the page, keeping its metadata.
+    pub fn try_map<U, E, F>(self, f: F) -> Result<Page<U>, E>
+    where
+        F: FnOnce(Vec<T>) -> Result<Vec<U>, E>,
+    {
+        Ok(Page {
+            items: f(self.items)?,
+            metadata: self.metadata,
+        })
+    }
+}
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct PageMetadata {
+    /// Number of resources matching the request, regardless of the pagination.
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+    /// Cursor to send as `after` to fetch the next page, absent on the last page and on sorted listings.
+    pub next_cursor: Option<i64>,
+    /// Offset of the next page of sorted listings, which have no cursor.
+    #[serde(skip)]
+    pub next_offset: Option<i64>,
+}
+
+pub trait Paginate: AsQuery + Sized {
+    /// Restricts the query to a page of resources ordered by the `cursor` column, which must be a `BIGINT`.
+    fn paginate<C: Column>(self, cursor: C, pagination: Pagination) -> Paginated<Self::Query>;
+}
+
+impl<T: AsQuery> Paginate for T {
+    fn paginate<C: Column>(self, _cursor: C, pagination: Pagination) -> Paginated<Self::Query> {
+        Paginated {
+            query: self.as_query(),
+            cursor: C::NAME,
+            pagination,
+        }
+    }
+}
+
+/// Query wrapper loading a page alongside the number of resources matching the query and following the cursor.
+#[derive(Clone, Debug)]
+pub struct Paginated<T> {
+    query: T,
+    cursor: &'static str,
+    pagination: Pagination,
+}
+
+impl<T> Paginated<T> {
+    pub fn load_page<R>(self, connection: &Connection) -> QueryResult<Page<R>>
+    where
+        T: Query + QueryFragment<Pg>,
+        Pg: HasSqlType<T::SqlType>,
+        (R, i64, i64, i64): Queryable<(T::SqlType, BigInt, BigInt, BigInt), Pg>,
+    {
+        let Pagination { limit, offset, .. } = self.pagination;
+        let sorted = !self.pagination.sort.is_empty();
+        let rows = (&self).load::<(R, i64, i64, i64)>(connection)?;
+
+        // Counts are carried by the rows, hence pages past the end need counting on their own.
+        let (total, remaining) = match rows.first() {
+            Some((_, total, _, remaining)) => (*total, *remaining),
+            None => (Count { query: &self.query }.get_result(connection)?, 0),
+        };
+        let has_next = offset + (rows.len() as i64) < remaining;
+        let next_cursor = match rows.last() {
+            Some((_, _, cursor, _)) if !sorted && has_next => Some(*cursor),
+            _ => None,
+        };
+
+        Ok(Page {
+            items: rows.into_iter().map(|(item, ..)| item).collect(),
+            metadata: PageMetadata {
+                total,
+                limit,
+                offset,
+                next_cursor,
+                next_offset: if sorted && has_next { Some(offset + limit) } else { None },
+            },
+        })
+    }
+}
+
+// The SQL depends on the cursor and the sorting, so the prepared statement cannot be cached by type.
+impl<T> QueryId for Paginated<T> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<T: Query> Query for Paginated<T> {
+    type SqlType = (T::SqlType, BigInt, BigInt, BigInt);
+}
+
+impl<T> RunQueryDsl<Connection> for &Paginated<T> {}
+
+impl<T> QueryFragment<Pg> for Paginated<T>
+where
+    T: QueryFragment<Pg>,
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        // The total is counted before the cursor filter whereas the remaining resources are counted after it.
+        out.push_sql("SELECT *, ");
+        out.push_identifier(self.cursor)?;
+        out.push_sql(", COUNT(*) OVER () FROM (SELECT *, COUNT(*) OVER () FROM (");
+        self.query.walk_ast(out.reborrow())?;
+        out.push_sql(") AS query) AS counted");
+
+        if let Some(after) = &self.pagination.after {
+            out.push_sql(" WHERE ");
+            out.push_identifier(self.cursor)?;
+            out.push_sql(" > ");
+            out.push_bind_param::<BigInt, _>(after)?;
+        }
+
+        // The cursor column comes last to break ties between sorted columns.
+        out.push_sql(" ORDER BY ");
+        for (column, direction) in &self.pagination.sort {
+            out.push_identifier(column)?;
+            out.push_sql(match direction {
+                Direction::Ascending => " ASC, ",
+                Direction::Descending => " DESC, ",
+            });
+        }
+        out.push_identifier(self.cursor)?;
+        out.push_sql(" LIMIT ");
+        out.push_bind_param::<BigInt, _>(&self.pagination.limit)?;
+        out.push_sql(" OFFSET ");
+        out.push_bind_param::<BigInt, _>(&self.pagination.offset)?;
+
+        Ok(())
+    }
+}
+
+/// Query counting the resources matching the wrapped query.
+struct Count<'a, T> {
+    query: &'a T,
+}
+
+impl<T> QueryId for Count<'_, T> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<T> Query for Count<'_, T> {
+    type SqlType = BigInt;
+}
+
+impl<T> RunQueryDsl<Connection> for Count<'_, T> {}
+
+impl<T> QueryFragment<Pg> for Count<'_, T>
+where
+    T: QueryFragment<Pg>,
+{
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.push_sql("SELECT COUNT(*) FROM (");
+        self.query.walk_ast(out.reborrow())?;
+        out.push_sql(") AS query");
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_defaults_to_the_first_page() {
+        let pagination = Pagination::new(None, None, None);
+
+        assert_eq!(pagination.limit(), DEFAULT_LIMIT);
+        assert_eq!(pagination.offset(), 0);
+    }
+
+    #[test]
+    fn new_bounds_the_limit_and_the_offset() {
+        assert_eq!(Pagination::new(Some(0), None, None).limit(), 1);
+        assert_eq!(Pagination::new(Some(MAX_LIMIT + 1), None, None).limit(), MAX_LIMIT);
+        assert_eq!(Pagination::new(None, Some(-1), None).offset(), 0);
+    }
+
+    const COLUMNS: &[&str] = &["vacancy_id", "job_title", "start_date"];
+
+    #[test]
+    fn sorted_by_parses_directions() {
+        let pagination = Pagination::new(None, None, None)
+            .sorted_by(Some("-start_date, +job_title,vacancy_id"), COLUMNS)
+            .unwrap();
+
+        assert_eq!(
+            pagination.sort,
+            vec![
+                ("start_date", Direction::Descending),
+                ("job_title", Direction::Ascending),
+                ("vacancy_id", Direction::Ascending),
+            ]
+        );
+    }
+
+    #[test]
+    fn sorted_by_ignores_empty_fields() {
+        for sort in [None, Some(""), Some(" , ")] {
+            let pagination = Pagination::new(None, None, None).sorted_by(sort, COLUMNS).unwrap();
+
+            assert!(pagination.sort.is_empty());
+        }
+    }
+
+    #[test]
+    fn sorted_by_rejects_unknown_columns() {
+        let result = Pagination::new(None, None, None).sorted_by(Some("-salary"), COLUMNS);
+
+        assert!(matches!(result, Err(Error::BadRequest(message)) if message.contains("'salary'")));
+    }
+
+    #[test]
+    fn sorted_by_rejects_cursors() {
+        let result = Pagination::new(None, None, Some(42)).sorted_by(Some("job_title"), COLUMNS);
+
+        assert!(matches!(result, Err(Error::BadRequest(_))));
+        assert!(Pagination::new(None, None, Some(42)).sorted_by(None, COLUMNS).is_ok());
+    }
+}
@@ -0,0 +1,67 @@
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+
+use crate::Error;
+
+/// Deserializes a nullable field of a partial update, a field set to `null` becoming `Some(None)` so that it can be
+/// told from a missing field, which is `None` provided the field is also `#[serde(default)]`.
+pub fn nullable<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Deserializes a non-nullable field of a partial update, which can be missing but not set to `null`.
+pub fn non_null<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer)?
+        .map(Some)
+        .ok_or_else(|| D::Error::custom("Must not be null"))
+}
+
+/// Error answered to jobboards attempting to change the moderation flags of a resource, which only administrators can.
+pub fn moderation_forbidden() -> Error {
+    Error::Forbidden("Only administrators can change the 'verified' and 'active' flags".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    struct Patch {
+        #[serde(default, deserialize_with = "non_null")]
+        name: Option<String>,
+        #[serde(default, deserialize_with = "nullable")]
+        url: Option<Option<String>>,
+    }
+
+    #[test]
+    fn missing_fields_are_left_untouched() {
+        let patch = serde_json::from_str::<Patch>("{}").unwrap();
+
+        assert_eq!(patch.name, None);
+        assert_eq!(patch.url, None);
+    }
+
+    #[test]
+    fn nullable_clears_fields_set_to_null() {
+        let patch = serde_json::from_str::<Patch>(r#"{"name": "Acme", "url": null}"#).unwrap();
+
+        assert_eq!(patch.name.as_deref(), Some("Acme"));
+        assert_eq!(patch.url, Some(None));
+    }
+
+    #[test]
+    fn non_null_rejects_null() {
+        let e = serde_json::from_str::<Patch>(r#"{"name": null}"#).unwrap_err();
+
+        assert!(e.is_data());
+        assert!(e.to_string().starts_with("Must not be null"));
+    }
+}
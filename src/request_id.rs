@@ -0,0 +1,107 @@
+use std::fmt;
+
+use rand::rngs::OsRng;
+use rand::RngCore;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::{Data, Request, Response};
+
+const REQUEST_ID_HEADER: &str = "X-Request-Id";
+const MAX_LENGTH: usize = 128;
+
+/// Identifier of a request, which correlates a response with the log lines it caused.
+///
+/// The identifier sent by the client in the `X-Request-Id` header is kept provided it is made of at most 128
+/// alphanumeric characters, `-`, `_` or `.`, a random one being assigned otherwise.
+#[derive(Clone, Debug)]
+pub struct RequestId(String);
+
+impl RequestId {
+    /// Identifier of the request, assigned on first use.
+    pub fn of<'r>(request: &'r Request<'_>) -> &'r Self {
+        request.local_cache(|| match request.headers().get_one(REQUEST_ID_HEADER) {
+            Some(id) if is_valid(id) => Self(id.to_string()),
+            _ => Self::generate(),
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    fn generate() -> Self {
+        let mut bytes = [0; 16];
+        OsRng.fill_bytes(&mut bytes);
+
+        Self(hex::encode(bytes))
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+fn is_valid(id: &str) -> bool {
+    (1..=MAX_LENGTH).contains(&id.len())
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Fairing assigning an identifier to every request, which is sent back in the `X-Request-Id` header.
+pub fn fairing() -> impl Fairing {
+    RequestIdFairing
+}
+
+struct RequestIdFairing;
+
+#[rocket::async_trait]
+impl Fairing for RequestIdFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request ID",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _data: &mut Data<'_>) {
+        // Assigned upfront so that every log line of the request can be tagged with it.
+        RequestId::of(request);
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        response.set_raw_header(REQUEST_ID_HEADER, RequestId::of(request).to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_valid_accepts_and_rejects_identifiers() {
+        let long = "a".repeat(MAX_LENGTH);
+        let too_long = "a".repeat(MAX_LENGTH + 1);
+
+        for (id, valid) in [
+            ("a", true),
+            ("5f0c6a4e2b1d4c8f9a7e3d2c1b0a9f8e", true),
+            ("req-42_retry.1", true),
+            (long.as_str(), true),
+            ("", false),
+            (too_long.as_str(), false),
+            ("req 42", false),
+            ("req/42", false),
+            ("req\n42", false),
+            ("réq", false),
+        ] {
+            assert_eq!(is_valid(id), valid, "{:?}", id);
+        }
+    }
+
+    #[test]
+    fn generated_identifiers_are_valid() {
+        assert!(is_valid(RequestId::generate().as_str()));
+    }
+}
@@ -1,33 +1,109 @@
-use rocket::http::Status;
+use std::io::Cursor;
+
+use chrono::{DateTime, Utc};
+use rocket::http::uri::Origin;
+use rocket::http::{ContentType, Status};
 use rocket::response::{Responder, Response as RocketResponse, Result as ResponseResult};
-use rocket::serde::json::Json;
 use rocket::Request;
 use rocket_okapi::okapi::openapi3::Responses;
 use rocket_okapi::{gen::OpenApiGenerator, response::OpenApiResponderInner};
 use schemars::JsonSchema;
 use serde::Serialize;
 
+use crate::cache::{self, Validators};
 use crate::error::Error;
+use crate::pagination::{Page, PageMetadata};
+use crate::request_id::RequestId;
+use crate::validation::FieldError;
+use crate::version::Versioned;
+
+const PROBLEM_TYPE_PREFIX: &str = "urn:oh-platform:problem:";
 
 pub enum Response<T> {
-    Success { data: T, status: Status },
+    Success {
+        data: T,
+        status: Status,
+        /// Version of the resource, sent as its `ETag`.
+        version: Option<i32>,
+        last_modified: Option<DateTime<Utc>>,
+    },
+    Page {
+        data: T,
+        metadata: PageMetadata,
+        status: Status,
+    },
     Failure(Error),
 }
 
 impl<'r, T: Serialize> Responder<'r, 'static> for Response<T> {
     fn respond_to(self, request: &'r Request<'_>) -> ResponseResult<'static> {
-        let (status, mut response) = match self {
-            Response::Success { data, status } => (
+        let (status, envelop, version, last_modified) = match self {
+            Response::Success {
+                data,
                 status,
-                RocketResponse::build_from(Json(DataEnvelop { data }).respond_to(request)?),
-            ),
-            Response::Failure(e) => (
-                e.get_http_status(),
-                RocketResponse::build_from(Json::<OpaqueError>(e.into()).respond_to(request)?),
-            ),
+                version,
+                last_modified,
+            } => (status, DataEnvelop { data, pagination: None }, version, last_modified),
+            Response::Page { data, metadata, status } => {
+                let pagination = PaginationMetadata {
+                    links: PageLinks::new(request.uri(), &metadata),
+                    page: metadata,
+                };
+
+                (
+                    status,
+                    DataEnvelop {
+                        data,
+                        pagination: Some(pagination),
+                    },
+                    None,
+                    None,
+                )
+            }
+            Response::Failure(e) => {
+                let status = e.get_http_status();
+
+                // Internal errors are only detailed in the logs, which are found back from the request id.
+                if status == Status::InternalServerError {
+                    tracing::error!(
+                        request_id = %RequestId::of(request),
+                        error = ?eyre::Report::new(e.clone()),
+                        "Request failed"
+                    );
+                }
+
+                let body = serde_json::to_string(&Problem::new(e, request)).map_err(|_| Status::InternalServerError)?;
+
+                return RocketResponse::build()
+                    .header(ContentType::new("application", "problem+json"))
+                    .sized_body(body.len(), Cursor::new(body))
+                    .status(status)
+                    .ok();
+            }
         };
 
-        response.status(status).ok()
+        let body = serde_json::to_string(&envelop).map_err(|_| Status::InternalServerError)?;
+        let validators = Validators::new(version, last_modified, &body);
+        let cacheable = cache::is_cacheable(request, status);
+        let mut response = RocketResponse::build();
+
+        if cacheable || version.is_some() {
+            validators.apply(&mut response);
+        }
+
+        if cacheable {
+            response.raw_header("Cache-Control", cache::cache_control(request));
+
+            if validators.is_fresh(request) {
+                return response.status(Status::NotModified).ok();
+            }
+        }
+
+        response
+            .header(ContentType::JSON)
+            .sized_body(body.len(), Cursor::new(body))
+            .status(status)
+            .ok()
     }
 }
 
@@ -47,8 +123,8 @@ where
 
         rocket_okapi::util::add_default_response_schema(
             &mut responses,
-            "application/json",
-            generator.json_schema::<OpaqueError>(),
+            "application/problem+json",
+            generator.json_schema::<Problem>(),
         );
 
         Ok(responses)
@@ -65,7 +141,56 @@ where
 {
     fn into_response(self, status: Status) -> Response<T> {
         match self {
-            Ok(data) => Response::Success { data, status },
+            Ok(data) => Response::Success {
+                data,
+                status,
+                version: None,
+                last_modified: None,
+            },
+            Err(e) => Response::Failure(e.into()),
+        }
+    }
+}
+
+pub trait IntoVersionedResponse<T> {
+    /// Same as [`IntoResponse::into_response`], the version of the resource being sent as its `ETag` along with its
+    /// `Last-Modified` when known.
+    fn into_versioned_response(self, status: Status) -> Response<T>;
+}
+
+impl<T, E> IntoVersionedResponse<T> for Result<T, E>
+where
+    T: Versioned,
+    E: Into<Error>,
+{
+    fn into_versioned_response(self, status: Status) -> Response<T> {
+        match self {
+            Ok(data) => Response::Success {
+                version: Some(data.version()),
+                last_modified: data.last_modified(),
+                data,
+                status,
+            },
+            Err(e) => Response::Failure(e.into()),
+        }
+    }
+}
+
+pub trait IntoPageResponse<T> {
+    fn into_page_response(self, status: Status) -> Response<Vec<T>>;
+}
+
+impl<T, E> IntoPageResponse<T> for Result<Page<T>, E>
+where
+    E: Into<Error>,
+{
+    fn into_page_response(self, status: Status) -> Response<Vec<T>> {
+        match self {
+            Ok(page) => Response::Page {
+                data: page.items,
+                metadata: page.metadata,
+                status,
+            },
             Err(e) => Response::Failure(e.into()),
         }
     }
@@ -74,19 +199,167 @@ where
 #[derive(JsonSchema, Clone, Serialize)]
 struct DataEnvelop<T> {
     data: T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pagination: Option<PaginationMetadata>,
+}
+
+#[derive(JsonSchema, Clone, Serialize)]
+struct PaginationMetadata {
+    #[serde(flatten)]
+    page: PageMetadata,
+    links: PageLinks,
+}
+
+#[derive(JsonSchema, Clone, Serialize)]
+struct PageLinks {
+    #[serde(rename = "self")]
+    current: String,
+    next: Option<String>,
+}
+
+impl PageLinks {
+    fn new(uri: &Origin<'_>, page: &PageMetadata) -> Self {
+        // Sorted listings have no cursor, their next page being fetched from its offset instead.
+        let next_page = match (page.next_cursor, page.next_offset) {
+            (Some(cursor), _) => Some(format!("after={}", cursor)),
+            (None, Some(offset)) => Some(format!("offset={}", offset)),
+            (None, None) => None,
+        };
+
+        let next = next_page.map(|next_page| {
+            // Any previous cursor or offset is replaced by the one of the next page.
+            let mut query = uri
+                .query()
+                .map(|query| {
+                    query
+                        .as_str()
+                        .split('&')
+                        .filter(|segment| !matches!(segment.split('=').next(), Some("" | "after" | "offset")))
+                        .map(str::to_string)
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            query.push(next_page);
+
+            format!("{}?{}", uri.path(), query.join("&"))
+        });
+
+        Self {
+            current: uri.to_string(),
+            next,
+        }
+    }
 }
 
+/// Error response following RFC 7807, sent as `application/problem+json`.
 #[derive(JsonSchema, Serialize)]
-struct OpaqueError {
-    error: String,
-    code: u16,
+struct Problem {
+    /// URI identifying the kind of problem, `urn:oh-platform:problem:<code>`.
+    #[serde(rename = "type")]
+    problem_type: String,
+    title: &'static str,
+    status: u16,
+    detail: String,
+    /// URI of the request which caused the problem.
+    instance: String,
+    /// Stable, machine-readable code of the problem.
+    code: &'static str,
+    /// Invalid or conflicting fields of the request body, if any.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<FieldError>,
+    /// Identifier of the request, also sent in the `X-Request-Id` header, to mention when reporting the problem.
+    request_id: String,
 }
 
-impl From<Error> for OpaqueError {
-    fn from(e: Error) -> Self {
+impl Problem {
+    fn new(e: Error, request: &Request<'_>) -> Self {
         Self {
-            error: e.to_string(),
-            code: e.get_http_status().code,
+            problem_type: format!("{}{}", PROBLEM_TYPE_PREFIX, e.code()),
+            title: e.title(),
+            status: e.get_http_status().code,
+            detail: e.detail(),
+            instance: request.uri().to_string(),
+            code: e.code(),
+            errors: match e {
+                Error::InvalidFields(errors) | Error::ConflictedFields(errors) => errors,
+                _ => Vec::new(),
+            },
+            request_id: RequestId::of(request).to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::http::Header;
+    use rocket::local::blocking::Client;
+    use serde_json::json;
+
+    use super::*;
+
+    fn page(next_cursor: Option<i64>, next_offset: Option<i64>) -> PageMetadata {
+        PageMetadata {
+            total: 100,
+            limit: 10,
+            offset: 20,
+            next_cursor,
+            next_offset,
         }
     }
+
+    #[test]
+    fn next_link_follows_the_cursor() {
+        let uri = Origin::parse("/v1/vacancy?limit=10&offset=20&after=5").unwrap();
+        let links = PageLinks::new(&uri, &page(Some(42), None));
+
+        assert_eq!(links.current, "/v1/vacancy?limit=10&offset=20&after=5");
+        assert_eq!(links.next.as_deref(), Some("/v1/vacancy?limit=10&after=42"));
+    }
+
+    #[test]
+    fn next_link_of_sorted_listings_follows_the_offset() {
+        let uri = Origin::parse("/v1/vacancy?sort=-start_date&limit=10&offset=20").unwrap();
+        let links = PageLinks::new(&uri, &page(None, Some(30)));
+
+        assert_eq!(
+            links.next.as_deref(),
+            Some("/v1/vacancy?sort=-start_date&limit=10&offset=30")
+        );
+    }
+
+    #[test]
+    fn last_page_has_no_next_link() {
+        let uri = Origin::parse("/v1/vacancy").unwrap();
+        let links = PageLinks::new(&uri, &page(None, None));
+
+        assert_eq!(links.current, "/v1/vacancy");
+        assert!(links.next.is_none());
+    }
+
+    #[test]
+    fn problem_identifies_the_error_and_the_request() {
+        let client = Client::untracked(rocket::build()).unwrap();
+        let request = client
+            .put("/v1/company/42")
+            .header(Header::new("X-Request-Id", "a1b2c3"));
+        let error = Error::ConflictedFields(vec![FieldError::new(
+            "company_name",
+            "taken",
+            "Company name is already taken",
+        )]);
+
+        assert_eq!(
+            serde_json::to_value(Problem::new(error, &request)).unwrap(),
+            json!({
+                "type": "urn:oh-platform:problem:conflicted_fields",
+                "title": "Conflicted data",
+                "status": 409,
+                "detail": "Company name is already taken",
+                "instance": "/v1/company/42",
+                "code": "conflicted_fields",
+                "errors": [{ "field": "company_name", "code": "taken", "message": "Company name is already taken" }],
+                "request_id": "a1b2c3"
+            })
+        );
+    }
 }
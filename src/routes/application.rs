@@ -1,14 +1,39 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
+
+use chrono::{DateTime, Utc};
+use diesel::deserialize::{self, FromSql};
+use diesel::dsl::{now, Eq, Filter, IsNull};
+use diesel::pg::Pg;
+use diesel::result::QueryResult;
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::Text;
+use diesel::{Connection as _, ExpressionMethods, QueryDsl, RunQueryDsl};
 use rocket::http::Status;
-use rocket::serde::json::Json;
-use rocket::{delete, get, post, put};
+use rocket::{delete, get, patch, post, put};
 use rocket_okapi::openapi;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::response::IntoResponse;
-use crate::schema::application;
+use super::company::Company;
+use super::jobboard::Jobboard;
+use super::vacancy::{fill_if_staffed, tenant_vacancies, Vacancy, VacancyStatus};
+use crate::database::Connection;
+use crate::form::Timestamp;
+use crate::include::Include;
+use crate::pagination::{ListQuery, Paginate};
+use crate::partial;
+use crate::response::{IntoPageResponse, IntoResponse, IntoVersionedResponse};
 use crate::schema::application::dsl::application as application_table;
-use crate::{Database, Response};
+use crate::schema::application_history::dsl::application_history as application_history_table;
+use crate::schema::company::dsl::company as company_table;
+use crate::schema::jobboard::dsl::jobboard as jobboard_table;
+use crate::schema::vacancy::dsl::vacancy as vacancy_table;
+use crate::schema::{application, application_history, company, jobboard, vacancy};
+use crate::validation::{Field, FieldError, Validate, Validator};
+use crate::version::{check_version, Versioned};
+use crate::{Database, Error, IfMatch, Response, Tenant, Validated};
 
 #[derive(JsonSchema, Queryable, Serialize)]
 pub struct Application {
@@ -23,14 +48,141 @@ pub struct Application {
     url_extra_2: Option<String>,
     url_extra_3: Option<String>,
     verified: bool,
-    status: String,
+    status: ApplicationStatus,
+    version: i32,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    deleted_at: Option<DateTime<Utc>>,
+}
+
+impl Versioned for Application {
+    fn version(&self) -> i32 {
+        self.version
+    }
+
+    fn last_modified(&self) -> Option<DateTime<Utc>> {
+        Some(self.updated_at)
+    }
+}
+
+/// Stage of an application in the hiring workflow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, JsonSchema, Deserialize, Serialize, AsExpression, FromSqlRow)]
+#[serde(rename_all = "snake_case")]
+#[sql_type = "Text"]
+pub enum ApplicationStatus {
+    Received,
+    Screening,
+    Interview,
+    Offer,
+    Hired,
+    Rejected,
+    Withdrawn,
+}
+
+impl ApplicationStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Received => "received",
+            Self::Screening => "screening",
+            Self::Interview => "interview",
+            Self::Offer => "offer",
+            Self::Hired => "hired",
+            Self::Rejected => "rejected",
+            Self::Withdrawn => "withdrawn",
+        }
+    }
+
+    fn can_transition_to(self, next: Self) -> bool {
+        use ApplicationStatus::*;
+
+        self == next
+            || matches!(
+                (self, next),
+                (Received, Screening)
+                    | (Screening, Interview)
+                    | (Interview, Offer)
+                    | (Offer, Hired)
+                    | (Received | Screening | Interview | Offer, Rejected | Withdrawn)
+            )
+    }
+}
+
+impl fmt::Display for ApplicationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl ToSql<Text, Pg> for ApplicationStatus {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Pg>) -> serialize::Result {
+        <str as ToSql<Text, Pg>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Pg> for ApplicationStatus {
+    fn from_sql(bytes: Option<&[u8]>) -> deserialize::Result<Self> {
+        match <String as FromSql<Text, Pg>>::from_sql(bytes)?.as_str() {
+            "received" => Ok(Self::Received),
+            "screening" => Ok(Self::Screening),
+            "interview" => Ok(Self::Interview),
+            "offer" => Ok(Self::Offer),
+            "hired" => Ok(Self::Hired),
+            "rejected" => Ok(Self::Rejected),
+            "withdrawn" => Ok(Self::Withdrawn),
+            status => Err(format!("Unknown application status '{}'", status).into()),
+        }
+    }
+}
+
+/// Status change of an application.
+#[derive(JsonSchema, Queryable, Serialize)]
+pub struct ApplicationHistory {
+    history_id: i64,
+    application_id: i64,
+    from_status: ApplicationStatus,
+    to_status: ApplicationStatus,
+    /// Who changed the status, e.g. `jobboard:42`.
+    actor: String,
+    changed_at: DateTime<Utc>,
+}
+
+#[derive(Insertable)]
+#[table_name = "application_history"]
+struct NewApplicationHistory {
+    application_id: i64,
+    from_status: ApplicationStatus,
+    to_status: ApplicationStatus,
+    actor: String,
+}
+
+/// Application alongside the related resources requested through the `include` query parameter.
+#[derive(JsonSchema, Serialize)]
+pub struct ApplicationWithRelations {
+    #[serde(flatten)]
+    application: Application,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vacancy: Option<Vacancy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    company: Option<Company>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    jobboard: Option<Jobboard>,
+}
+
+/// Versioned as the application itself, which only identifies responses embedding no relation.
+impl Versioned for ApplicationWithRelations {
+    fn version(&self) -> i32 {
+        self.application.version()
+    }
+
+    fn last_modified(&self) -> Option<DateTime<Utc>> {
+        self.application.last_modified()
+    }
 }
 
 #[derive(JsonSchema, Deserialize, Insertable)]
 #[serde(deny_unknown_fields)]
 #[table_name = "application"]
 pub struct NewApplication {
-    jobboard_id: i64,
     vacancy_id: i64,
     first_name: Option<String>,
     last_name: String,
@@ -41,56 +193,615 @@ pub struct NewApplication {
     url_extra_3: Option<String>,
 }
 
+impl Validate for NewApplication {
+    fn validate(&self, validator: &mut Validator) {
+        validate_candidate(
+            validator,
+            &self.first_name,
+            &self.last_name,
+            &self.email,
+            &self.url_resume,
+            &self.url_extra_1,
+            &self.url_extra_2,
+            &self.url_extra_3,
+        );
+    }
+}
+
+/// Application created through its vacancy, the vacancy being given by the URL.
+#[derive(JsonSchema, Deserialize, Insertable)]
+#[serde(deny_unknown_fields)]
+#[table_name = "application"]
+pub struct NewVacancyApplication {
+    first_name: Option<String>,
+    last_name: String,
+    email: Option<String>,
+    url_resume: Option<String>,
+    url_extra_1: Option<String>,
+    url_extra_2: Option<String>,
+    url_extra_3: Option<String>,
+}
+
+impl Validate for NewVacancyApplication {
+    fn validate(&self, validator: &mut Validator) {
+        validate_candidate(
+            validator,
+            &self.first_name,
+            &self.last_name,
+            &self.email,
+            &self.url_resume,
+            &self.url_extra_1,
+            &self.url_extra_2,
+            &self.url_extra_3,
+        );
+    }
+}
+
 #[derive(JsonSchema, Deserialize, AsChangeset)]
 #[serde(deny_unknown_fields)]
 #[table_name = "application"]
 pub struct ApplicationChangeset {
     verified: bool,
-    status: String,
+    status: ApplicationStatus,
 }
 
-#[openapi(tag = "Application")]
-#[get("/application")]
-pub async fn get_all_applications(database: Database) -> Response<Vec<Application>> {
-    database.get_all(application_table).await.into_response(Status::Ok)
+impl Validate for ApplicationChangeset {}
+
+/// Partial update of the candidate details of an application: missing fields are left untouched and nullable fields
+/// are cleared when set to `null`.
+#[derive(JsonSchema, Deserialize, AsChangeset)]
+#[serde(deny_unknown_fields)]
+#[table_name = "application"]
+pub struct ApplicationPatch {
+    #[serde(default, deserialize_with = "partial::nullable")]
+    first_name: Option<Option<String>>,
+    #[serde(default, deserialize_with = "partial::non_null")]
+    last_name: Option<String>,
+    #[serde(default, deserialize_with = "partial::nullable")]
+    email: Option<Option<String>>,
+    #[serde(default, deserialize_with = "partial::nullable")]
+    url_resume: Option<Option<String>>,
+    #[serde(default, deserialize_with = "partial::nullable")]
+    url_extra_1: Option<Option<String>>,
+    #[serde(default, deserialize_with = "partial::nullable")]
+    url_extra_2: Option<Option<String>>,
+    #[serde(default, deserialize_with = "partial::nullable")]
+    url_extra_3: Option<Option<String>>,
+}
+
+impl Validate for ApplicationPatch {
+    fn validate(&self, validator: &mut Validator) {
+        validate_candidate(
+            validator.not_empty(self.is_empty()),
+            &self.first_name,
+            &self.last_name,
+            &self.email,
+            &self.url_resume,
+            &self.url_extra_1,
+            &self.url_extra_2,
+            &self.url_extra_3,
+        );
+    }
+}
+
+impl ApplicationPatch {
+    fn is_empty(&self) -> bool {
+        self.first_name.is_none()
+            && self.last_name.is_none()
+            && self.email.is_none()
+            && self.url_resume.is_none()
+            && self.url_extra_1.is_none()
+            && self.url_extra_2.is_none()
+            && self.url_extra_3.is_none()
+    }
+}
+
+/// Checks the candidate details shared by the bodies creating and changing applications, which are only checked when
+/// set.
+#[allow(clippy::too_many_arguments)]
+fn validate_candidate(
+    validator: &mut Validator,
+    first_name: &impl Field<str>,
+    last_name: &impl Field<str>,
+    email: &impl Field<str>,
+    url_resume: &impl Field<str>,
+    url_extra_1: &impl Field<str>,
+    url_extra_2: &impl Field<str>,
+    url_extra_3: &impl Field<str>,
+) {
+    validator
+        .max_length("first_name", first_name, 255)
+        .required("last_name", last_name)
+        .max_length("last_name", last_name, 255)
+        .max_length("email", email, 255)
+        .email("email", email)
+        .max_length("url_resume", url_resume, 255)
+        .url("url_resume", url_resume)
+        .max_length("url_extra_1", url_extra_1, 255)
+        .url("url_extra_1", url_extra_1)
+        .max_length("url_extra_2", url_extra_2, 255)
+        .url("url_extra_2", url_extra_2)
+        .max_length("url_extra_3", url_extra_3, 255)
+        .url("url_extra_3", url_extra_3);
+}
+
+const APPLICATION_SORTABLE_COLUMNS: &[&str] = &[
+    "application_id",
+    "vacancy_id",
+    "first_name",
+    "last_name",
+    "status",
+    "verified",
+    "created_at",
+    "updated_at",
+];
+
+const APPLICATION_RELATIONS: &[&str] = &["vacancy", "company", "jobboard"];
+
+pub(crate) type LiveApplications = Filter<application_table, IsNull<application::deleted_at>>;
+
+/// Restricts the application table to the rows which are not soft deleted.
+pub(crate) fn live_applications() -> LiveApplications {
+    application_table.filter(application::deleted_at.is_null())
+}
+
+pub(crate) type TenantApplications = Filter<LiveApplications, Eq<application::jobboard_id, i64>>;
+
+/// Restricts the application table to the live rows owned by the given tenant.
+pub(crate) fn tenant_applications(tenant: Tenant) -> TenantApplications {
+    live_applications().filter(application::jobboard_id.eq(tenant.jobboard_id()))
+}
+
+/// Applications of the tenant to list, the deleted ones included when syncing the changes made since `updated_since`.
+fn listed_applications(tenant: Tenant, updated_since: Option<Timestamp>) -> application::BoxedQuery<'static, Pg> {
+    match updated_since {
+        Some(Timestamp(updated_since)) => application_table
+            .filter(application::jobboard_id.eq(tenant.jobboard_id()))
+            .filter(application::updated_at.ge(updated_since))
+            .into_boxed(),
+        None => tenant_applications(tenant).into_boxed(),
+    }
+}
+
+/// Locks the vacancy until the end of the transaction, failing unless it is published. Applications created
+/// meanwhile are therefore serialized with the hires filling the vacancy, which lock it as well.
+fn lock_open_vacancy(connection: &Connection, tenant: Tenant, vacancy_id: i64) -> Result<(), Error> {
+    let status = tenant_vacancies(tenant)
+        .find(vacancy_id)
+        .select(vacancy::status)
+        .for_share()
+        .first::<VacancyStatus>(connection)?;
+
+    check_open_vacancy(vacancy_id, status)
+}
+
+fn check_open_vacancy(vacancy_id: i64, status: VacancyStatus) -> Result<(), Error> {
+    match status {
+        VacancyStatus::Published => Ok(()),
+        status => Err(Error::ConflictedData(format!(
+            "Vacancy '{}' is {} and does not accept applications",
+            vacancy_id, status
+        ))),
+    }
+}
+
+/// Embeds the related resources requested by `include` into the applications, each relation being loaded with a
+/// single join whatever the number of applications. Deleted resources are left out.
+fn embed_relations(
+    connection: &Connection,
+    applications: Vec<Application>,
+    include: &Include,
+) -> QueryResult<Vec<ApplicationWithRelations>> {
+    let application_ids = applications
+        .iter()
+        .map(|application| application.application_id)
+        .collect::<Vec<_>>();
+
+    let mut vacancies = if include.contains("vacancy") {
+        application_table
+            .inner_join(vacancy_table)
+            .filter(application::application_id.eq_any(&application_ids))
+            .filter(vacancy::deleted_at.is_null())
+            .select((application::application_id, vacancy::all_columns))
+            .load::<(i64, Vacancy)>(connection)?
+            .into_iter()
+            .collect::<HashMap<_, _>>()
+    } else {
+        HashMap::new()
+    };
+
+    let mut companies = if include.contains("company") {
+        application_table
+            .inner_join(vacancy_table.inner_join(company_table))
+            .filter(application::application_id.eq_any(&application_ids))
+            .filter(company::deleted_at.is_null())
+            .select((application::application_id, company::all_columns))
+            .load::<(i64, Company)>(connection)?
+            .into_iter()
+            .collect::<HashMap<_, _>>()
+    } else {
+        HashMap::new()
+    };
+
+    let mut jobboards = if include.contains("jobboard") {
+        application_table
+            .inner_join(jobboard_table)
+            .filter(application::application_id.eq_any(&application_ids))
+            .filter(jobboard::deleted_at.is_null())
+            .select((application::application_id, jobboard::all_columns))
+            .load::<(i64, Jobboard)>(connection)?
+            .into_iter()
+            .collect::<HashMap<_, _>>()
+    } else {
+        HashMap::new()
+    };
+
+    Ok(applications
+        .into_iter()
+        .map(|application| ApplicationWithRelations {
+            vacancy: vacancies.remove(&application.application_id),
+            company: companies.remove(&application.application_id),
+            jobboard: jobboards.remove(&application.application_id),
+            application,
+        })
+        .collect())
 }
 
 #[openapi(tag = "Application")]
-#[post("/application", data = "<new_application>")]
-pub async fn add_new_application(new_application: Json<NewApplication>, database: Database) -> Response<Application> {
+#[get("/application?<include>&<query..>")]
+pub async fn get_all_applications(
+    include: Option<String>,
+    query: ListQuery,
+    tenant: Tenant,
+    database: Database,
+) -> Response<Vec<ApplicationWithRelations>> {
+    let pagination = match query.pagination(APPLICATION_SORTABLE_COLUMNS) {
+        Ok(pagination) => pagination,
+        Err(e) => return Response::Failure(e),
+    };
+
+    let include = match Include::parse(include.as_deref(), APPLICATION_RELATIONS) {
+        Ok(include) => include,
+        Err(e) => return Response::Failure(e),
+    };
+
+    let updated_since = query.updated_since();
+
     database
-        .create(application_table, new_application.into_inner())
+        .run(move |connection| {
+            listed_applications(tenant, updated_since)
+                .paginate(application::application_id, pagination)
+                .load_page(connection)?
+                .try_map(|applications| embed_relations(connection, applications, &include))
+        })
         .await
-        .into_response(Status::Created)
+        .into_page_response(Status::Ok)
 }
 
 #[openapi(tag = "Application")]
-#[get("/application/<application_id>")]
-pub async fn get_application(application_id: i64, database: Database) -> Response<Application> {
+#[post("/application", data = "<new_application>")]
+pub async fn add_new_application(
+    new_application: Validated<NewApplication>,
+    tenant: Tenant,
+    database: Database,
+) -> Response<Application> {
+    let new_application = new_application.into_inner();
+
     database
-        .get(application_table, application_id)
+        .run(move |connection| {
+            connection.transaction::<_, Error, _>(|| {
+                match lock_open_vacancy(connection, tenant, new_application.vacancy_id) {
+                    Err(Error::NotFound) => {
+                        return Err(Error::InvalidFields(vec![FieldError::new(
+                            "vacancy_id",
+                            "unknown_reference",
+                            "Must refer to an existing vacancy",
+                        )]))
+                    }
+                    result => result?,
+                }
+
+                diesel::insert_into(application_table)
+                    .values((application::jobboard_id.eq(tenant.jobboard_id()), new_application))
+                    .get_result::<Application>(connection)
+                    .map_err(Error::from)
+            })
+        })
         .await
-        .into_response(Status::Ok)
+        .into_versioned_response(Status::Created)
+}
+
+#[openapi(tag = "Application")]
+#[get("/application/<application_id>?<include>")]
+pub async fn get_application(
+    application_id: i64,
+    include: Option<String>,
+    tenant: Tenant,
+    database: Database,
+) -> Response<ApplicationWithRelations> {
+    let include = match Include::parse(include.as_deref(), APPLICATION_RELATIONS) {
+        Ok(include) => include,
+        Err(e) => return Response::Failure(e),
+    };
+
+    // Embedded resources change independently of the application, whose version then no longer identifies the
+    // response.
+    let versioned = include.is_empty();
+    let application = database
+        .run(move |connection| {
+            let application = tenant_applications(tenant)
+                .find(application_id)
+                .first::<Application>(connection)?;
+
+            embed_relations(connection, vec![application], &include).map(|mut applications| applications.remove(0))
+        })
+        .await;
+
+    if versioned {
+        application.into_versioned_response(Status::Ok)
+    } else {
+        application.into_response(Status::Ok)
+    }
 }
 
 #[openapi(tag = "Application")]
 #[put("/application/<application_id>", data = "<application_changeset>")]
 pub async fn update_application(
     application_id: i64,
-    application_changeset: Json<ApplicationChangeset>,
+    application_changeset: Validated<ApplicationChangeset>,
+    tenant: Tenant,
+    if_match: IfMatch,
+    database: Database,
+) -> Response<Application> {
+    let application_changeset = application_changeset.into_inner();
+
+    database
+        .run(move |connection| {
+            connection.transaction::<_, Error, _>(|| {
+                let (status, version) = tenant_applications(tenant)
+                    .find(application_id)
+                    .select((application::status, application::version))
+                    .for_update()
+                    .first::<(ApplicationStatus, i32)>(connection)?;
+
+                check_version(if_match.version(), version)?;
+
+                if !status.can_transition_to(application_changeset.status) {
+                    return Err(Error::InvalidData(format!(
+                        "Cannot move an application from '{}' to '{}'",
+                        status, application_changeset.status
+                    )));
+                }
+
+                let application = diesel::update(application_table.find(application_id))
+                    .set(&application_changeset)
+                    .get_result::<Application>(connection)?;
+
+                if status != application_changeset.status && application_changeset.status == ApplicationStatus::Hired {
+                    fill_if_staffed(connection, application.vacancy_id)?;
+                }
+
+                if status != application_changeset.status {
+                    diesel::insert_into(application_history_table)
+                        .values(NewApplicationHistory {
+                            application_id,
+                            from_status: status,
+                            to_status: application_changeset.status,
+                            actor: format!("jobboard:{}", tenant.jobboard_id()),
+                        })
+                        .execute(connection)?;
+                }
+
+                Ok(application)
+            })
+        })
+        .await
+        .into_versioned_response(Status::Ok)
+}
+
+#[openapi(tag = "Application")]
+#[patch("/application/<application_id>", data = "<application_patch>")]
+pub async fn patch_application(
+    application_id: i64,
+    application_patch: Validated<ApplicationPatch>,
+    tenant: Tenant,
+    if_match: IfMatch,
     database: Database,
 ) -> Response<Application> {
     database
-        .update(application_table, application_id, application_changeset.into_inner())
+        .update(
+            tenant_applications(tenant),
+            application_id,
+            if_match.version(),
+            application_patch.into_inner(),
+        )
+        .await
+        .into_versioned_response(Status::Ok)
+}
+
+#[openapi(tag = "Application")]
+#[get("/application/<application_id>/history")]
+pub async fn get_application_history(
+    application_id: i64,
+    tenant: Tenant,
+    database: Database,
+) -> Response<Vec<ApplicationHistory>> {
+    database
+        .run(move |connection| {
+            tenant_applications(tenant)
+                .find(application_id)
+                .select(application::application_id)
+                .first::<i64>(connection)?;
+
+            application_history_table
+                .filter(application_history::application_id.eq(application_id))
+                .order((application_history::changed_at, application_history::history_id))
+                .load::<ApplicationHistory>(connection)
+        })
         .await
         .into_response(Status::Ok)
 }
 
 #[openapi(tag = "Application")]
 #[delete("/application/<application_id>")]
-pub async fn delete_application(application_id: i64, database: Database) -> Response<()> {
+pub async fn delete_application(
+    application_id: i64,
+    tenant: Tenant,
+    if_match: IfMatch,
+    database: Database,
+) -> Response<()> {
     database
-        .delete(application_table, application_id)
+        .delete::<_, _, _, Application>(
+            tenant_applications(tenant),
+            application_id,
+            if_match.version(),
+            application::deleted_at.eq(now),
+        )
         .await
         .into_response(Status::NoContent)
 }
+
+#[openapi(tag = "Application")]
+#[post("/application/<application_id>/restore")]
+pub async fn restore_application(application_id: i64, tenant: Tenant, database: Database) -> Response<Application> {
+    database
+        .restore(
+            application_table
+                .filter(application::jobboard_id.eq(tenant.jobboard_id()))
+                .filter(application::deleted_at.is_not_null()),
+            application_id,
+            application::deleted_at.eq(None::<DateTime<Utc>>),
+        )
+        .await
+        .into_versioned_response(Status::Ok)
+}
+
+#[openapi(tag = "Application")]
+#[get("/vacancy/<vacancy_id>/application?<include>&<query..>")]
+pub async fn get_vacancy_applications(
+    vacancy_id: i64,
+    include: Option<String>,
+    query: ListQuery,
+    tenant: Tenant,
+    database: Database,
+) -> Response<Vec<ApplicationWithRelations>> {
+    let pagination = match query.pagination(APPLICATION_SORTABLE_COLUMNS) {
+        Ok(pagination) => pagination,
+        Err(e) => return Response::Failure(e),
+    };
+
+    let include = match Include::parse(include.as_deref(), APPLICATION_RELATIONS) {
+        Ok(include) => include,
+        Err(e) => return Response::Failure(e),
+    };
+
+    if let Err(e) = database
+        .get::<_, _, i64>(tenant_vacancies(tenant).select(vacancy::vacancy_id), vacancy_id)
+        .await
+    {
+        return Response::Failure(e.into());
+    }
+
+    let updated_since = query.updated_since();
+
+    database
+        .run(move |connection| {
+            listed_applications(tenant, updated_since)
+                .filter(application::vacancy_id.eq(vacancy_id))
+                .paginate(application::application_id, pagination)
+                .load_page(connection)?
+                .try_map(|applications| embed_relations(connection, applications, &include))
+        })
+        .await
+        .into_page_response(Status::Ok)
+}
+
+#[openapi(tag = "Application")]
+#[post("/vacancy/<vacancy_id>/application", data = "<new_application>")]
+pub async fn add_new_vacancy_application(
+    vacancy_id: i64,
+    new_application: Validated<NewVacancyApplication>,
+    tenant: Tenant,
+    database: Database,
+) -> Response<Application> {
+    let new_application = new_application.into_inner();
+
+    database
+        .run(move |connection| {
+            connection.transaction::<_, Error, _>(|| {
+                lock_open_vacancy(connection, tenant, vacancy_id)?;
+
+                diesel::insert_into(application_table)
+                    .values((
+                        application::jobboard_id.eq(tenant.jobboard_id()),
+                        application::vacancy_id.eq(vacancy_id),
+                        new_application,
+                    ))
+                    .get_result::<Application>(connection)
+                    .map_err(Error::from)
+            })
+        })
+        .await
+        .into_versioned_response(Status::Created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ApplicationStatus::*;
+    use super::*;
+
+    const STATUSES: [ApplicationStatus; 7] = [Received, Screening, Interview, Offer, Hired, Rejected, Withdrawn];
+
+    #[test]
+    fn paused_vacancies_do_not_accept_applications() {
+        assert!(check_open_vacancy(42, VacancyStatus::Published).is_ok());
+
+        let result = check_open_vacancy(42, VacancyStatus::Paused);
+        assert!(matches!(result, Err(Error::ConflictedData(message)) if message.contains("is paused")));
+    }
+
+    #[test]
+    fn applications_move_forward_one_step_at_a_time() {
+        assert!(Received.can_transition_to(Screening));
+        assert!(Screening.can_transition_to(Interview));
+        assert!(Interview.can_transition_to(Offer));
+        assert!(Offer.can_transition_to(Hired));
+
+        assert!(!Received.can_transition_to(Interview));
+        assert!(!Received.can_transition_to(Hired));
+        assert!(!Offer.can_transition_to(Screening));
+    }
+
+    #[test]
+    fn open_applications_can_be_rejected_or_withdrawn() {
+        for status in [Received, Screening, Interview, Offer] {
+            assert!(status.can_transition_to(Rejected), "{} should move to rejected", status);
+            assert!(
+                status.can_transition_to(Withdrawn),
+                "{} should move to withdrawn",
+                status
+            );
+        }
+    }
+
+    #[test]
+    fn closed_applications_are_final() {
+        for status in [Hired, Rejected, Withdrawn] {
+            for next in STATUSES.iter().filter(|next| **next != status) {
+                assert!(
+                    !status.can_transition_to(*next),
+                    "{} should not move to {}",
+                    status,
+                    next
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn keeping_the_same_status_is_allowed() {
+        for status in STATUSES {
+            assert!(status.can_transition_to(status));
+        }
+    }
+}
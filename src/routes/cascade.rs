@@ -0,0 +1,139 @@
+use diesel::dsl::{now, Eq, Filter, Select};
+use diesel::result::QueryResult;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+
+use super::application::live_applications;
+use super::company::live_companies;
+use super::vacancy::live_vacancies;
+use crate::database::Connection;
+use crate::schema::{application, company, vacancy};
+use crate::Error;
+
+/// Live resources depending on a jobboard or a company, which prevent deleting it unless the deletion is cascaded.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Dependents {
+    companies: i64,
+    vacancies: i64,
+    applications: i64,
+}
+
+impl Dependents {
+    /// Counts the live companies, vacancies and applications of the jobboard.
+    pub(crate) fn of_jobboard(connection: &Connection, jobboard_id: i64) -> QueryResult<Self> {
+        Ok(Self {
+            companies: live_companies()
+                .filter(company::jobboard_id.eq(jobboard_id))
+                .count()
+                .get_result(connection)?,
+            vacancies: live_vacancies()
+                .filter(vacancy::jobboard_id.eq(jobboard_id))
+                .count()
+                .get_result(connection)?,
+            applications: live_applications()
+                .filter(application::jobboard_id.eq(jobboard_id))
+                .count()
+                .get_result(connection)?,
+        })
+    }
+
+    /// Counts the live vacancies of the company and their live applications.
+    pub(crate) fn of_company(connection: &Connection, company_id: i64) -> QueryResult<Self> {
+        Ok(Self {
+            companies: 0,
+            vacancies: live_vacancies()
+                .filter(vacancy::company_id.eq(company_id))
+                .count()
+                .get_result(connection)?,
+            applications: live_applications()
+                .filter(application::vacancy_id.eq_any(company_vacancy_ids(company_id)))
+                .count()
+                .get_result(connection)?,
+        })
+    }
+
+    /// Fails with a conflict telling how many dependents block the deletion of the `resource`, if any.
+    pub(crate) fn ensure_none(self, resource: &str) -> Result<(), Error> {
+        let dependents = [
+            (self.companies, "company", "companies"),
+            (self.vacancies, "vacancy", "vacancies"),
+            (self.applications, "application", "applications"),
+        ]
+        .iter()
+        .filter(|(count, ..)| *count > 0)
+        .map(|(count, singular, plural)| format!("{} {}", count, if *count == 1 { singular } else { plural }))
+        .collect::<Vec<_>>();
+
+        if dependents.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::ConflictedData(format!(
+                "{} still has {}, delete them first or delete it with cascade=true",
+                resource,
+                dependents.join(", ")
+            )))
+        }
+    }
+}
+
+/// Soft deletes the live companies, vacancies and applications of the jobboard.
+pub(crate) fn delete_jobboard_dependents(connection: &Connection, jobboard_id: i64) -> QueryResult<()> {
+    diesel::update(live_applications().filter(application::jobboard_id.eq(jobboard_id)))
+        .set(application::deleted_at.eq(now))
+        .execute(connection)?;
+    diesel::update(live_vacancies().filter(vacancy::jobboard_id.eq(jobboard_id)))
+        .set(vacancy::deleted_at.eq(now))
+        .execute(connection)?;
+    diesel::update(live_companies().filter(company::jobboard_id.eq(jobboard_id)))
+        .set(company::deleted_at.eq(now))
+        .execute(connection)?;
+
+    Ok(())
+}
+
+/// Soft deletes the live vacancies of the company and their live applications.
+pub(crate) fn delete_company_dependents(connection: &Connection, company_id: i64) -> QueryResult<()> {
+    diesel::update(live_applications().filter(application::vacancy_id.eq_any(company_vacancy_ids(company_id))))
+        .set(application::deleted_at.eq(now))
+        .execute(connection)?;
+    diesel::update(live_vacancies().filter(vacancy::company_id.eq(company_id)))
+        .set(vacancy::deleted_at.eq(now))
+        .execute(connection)?;
+
+    Ok(())
+}
+
+type CompanyVacancyIds = Select<Filter<vacancy::table, Eq<vacancy::company_id, i64>>, vacancy::vacancy_id>;
+
+/// Identifiers of all the vacancies of the company, deleted or not, as a subquery.
+fn company_vacancy_ids(company_id: i64) -> CompanyVacancyIds {
+    vacancy::table
+        .filter(vacancy::company_id.eq(company_id))
+        .select(vacancy::vacancy_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_dependents_allow_deletion() {
+        assert!(Dependents::default().ensure_none("jobboard").is_ok());
+    }
+
+    #[test]
+    fn dependents_prevent_deletion() {
+        let dependents = Dependents {
+            companies: 1,
+            vacancies: 0,
+            applications: 3,
+        };
+
+        match dependents.ensure_none("jobboard") {
+            Err(Error::ConflictedData(reason)) => assert_eq!(
+                reason,
+                "jobboard still has 1 company, 3 applications, delete them first or delete it with cascade=true"
+            ),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+}
@@ -1,15 +1,23 @@
 use chrono::{DateTime, Utc};
+use diesel::dsl::{now, Eq, Filter, IsNull};
+use diesel::pg::Pg;
+use diesel::{Connection as _, ExpressionMethods, QueryDsl, RunQueryDsl};
 use rocket::http::Status;
-use rocket::serde::json::Json;
-use rocket::{delete, get, post, put};
+use rocket::{delete, get, patch, post, put};
 use rocket_okapi::openapi;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::response::IntoResponse;
+use super::cascade::{delete_company_dependents, Dependents};
+use crate::form::Timestamp;
+use crate::pagination::{ListQuery, Paginate};
+use crate::partial::{self, moderation_forbidden};
+use crate::response::{IntoPageResponse, IntoResponse, IntoVersionedResponse};
 use crate::schema::company;
 use crate::schema::company::dsl::company as company_table;
-use crate::{Database, Response};
+use crate::validation::{Validate, Validator};
+use crate::version::{check_bumped_version, Versioned};
+use crate::{Admin, Caller, Database, Error, IfMatch, Response, Tenant, Validated};
 
 #[derive(JsonSchema, Queryable, Serialize)]
 pub struct Company {
@@ -23,13 +31,26 @@ pub struct Company {
     timestamp: Option<DateTime<Utc>>,
     verified: bool,
     active: bool,
+    version: i32,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    deleted_at: Option<DateTime<Utc>>,
+}
+
+impl Versioned for Company {
+    fn version(&self) -> i32 {
+        self.version
+    }
+
+    fn last_modified(&self) -> Option<DateTime<Utc>> {
+        Some(self.updated_at)
+    }
 }
 
 #[derive(JsonSchema, Deserialize, Insertable)]
 #[serde(deny_unknown_fields)]
 #[table_name = "company"]
 pub struct NewCompany {
-    jobboard_id: i64,
     company_name: String,
     logo: Option<String>,
     website: String,
@@ -37,6 +58,20 @@ pub struct NewCompany {
     region: Option<String>,
 }
 
+impl Validate for NewCompany {
+    fn validate(&self, validator: &mut Validator) {
+        validator
+            .required("company_name", &self.company_name)
+            .max_length("company_name", &self.company_name, 255)
+            .max_length("logo", &self.logo, 255)
+            .url("logo", &self.logo)
+            .max_length("website", &self.website, 255)
+            .url("website", &self.website)
+            .max_length("description", &self.description, 255)
+            .max_length("region", &self.region, 255);
+    }
+}
+
 #[derive(JsonSchema, Deserialize, AsChangeset)]
 #[serde(deny_unknown_fields)]
 #[table_name = "company"]
@@ -45,45 +80,286 @@ pub struct CompanyChangeset {
     active: bool,
 }
 
+impl Validate for CompanyChangeset {}
+
+/// Partial update of a company: missing fields are left untouched and nullable fields are cleared when set to `null`.
+///
+/// Only administrators can change the `verified` and `active` flags.
+#[derive(JsonSchema, Deserialize, AsChangeset)]
+#[serde(deny_unknown_fields)]
+#[table_name = "company"]
+pub struct CompanyPatch {
+    #[serde(default, deserialize_with = "partial::non_null")]
+    company_name: Option<String>,
+    #[serde(default, deserialize_with = "partial::nullable")]
+    logo: Option<Option<String>>,
+    #[serde(default, deserialize_with = "partial::non_null")]
+    website: Option<String>,
+    #[serde(default, deserialize_with = "partial::nullable")]
+    description: Option<Option<String>>,
+    #[serde(default, deserialize_with = "partial::nullable")]
+    region: Option<Option<String>>,
+    #[serde(default, deserialize_with = "partial::non_null")]
+    verified: Option<bool>,
+    #[serde(default, deserialize_with = "partial::non_null")]
+    active: Option<bool>,
+}
+
+impl Validate for CompanyPatch {
+    fn validate(&self, validator: &mut Validator) {
+        validator
+            .not_empty(self.is_empty())
+            .required("company_name", &self.company_name)
+            .max_length("company_name", &self.company_name, 255)
+            .max_length("logo", &self.logo, 255)
+            .url("logo", &self.logo)
+            .max_length("website", &self.website, 255)
+            .url("website", &self.website)
+            .max_length("description", &self.description, 255)
+            .max_length("region", &self.region, 255);
+    }
+}
+
+impl CompanyPatch {
+    fn is_empty(&self) -> bool {
+        self.company_name.is_none()
+            && self.logo.is_none()
+            && self.website.is_none()
+            && self.description.is_none()
+            && self.region.is_none()
+            && !self.moderates()
+    }
+
+    fn moderates(&self) -> bool {
+        self.verified.is_some() || self.active.is_some()
+    }
+}
+
+const COMPANY_SORTABLE_COLUMNS: &[&str] = &[
+    "company_id",
+    "company_name",
+    "region",
+    "timestamp",
+    "verified",
+    "active",
+    "created_at",
+    "updated_at",
+];
+
+pub(crate) type LiveCompanies = Filter<company_table, IsNull<company::deleted_at>>;
+
+/// Restricts the company table to the rows which are not soft deleted.
+pub(crate) fn live_companies() -> LiveCompanies {
+    company_table.filter(company::deleted_at.is_null())
+}
+
+pub(crate) type TenantCompanies = Filter<LiveCompanies, Eq<company::jobboard_id, i64>>;
+
+/// Restricts the company table to the live rows owned by the given tenant.
+pub(crate) fn tenant_companies(tenant: Tenant) -> TenantCompanies {
+    live_companies().filter(company::jobboard_id.eq(tenant.jobboard_id()))
+}
+
+/// Companies of the jobboard to list, the deleted ones included when syncing the changes made since `updated_since`.
+fn listed_companies(jobboard_id: i64, updated_since: Option<Timestamp>) -> company::BoxedQuery<'static, Pg> {
+    match updated_since {
+        Some(Timestamp(updated_since)) => company_table
+            .filter(company::jobboard_id.eq(jobboard_id))
+            .filter(company::updated_at.ge(updated_since))
+            .into_boxed(),
+        None => live_companies()
+            .filter(company::jobboard_id.eq(jobboard_id))
+            .into_boxed(),
+    }
+}
+
 #[openapi(tag = "Company")]
-#[get("/company")]
-pub async fn get_all_companies(database: Database) -> Response<Vec<Company>> {
-    database.get_all(company_table).await.into_response(Status::Ok)
+#[get("/company?<query..>")]
+pub async fn get_all_companies(query: ListQuery, tenant: Tenant, database: Database) -> Response<Vec<Company>> {
+    let pagination = match query.pagination(COMPANY_SORTABLE_COLUMNS) {
+        Ok(pagination) => pagination,
+        Err(e) => return Response::Failure(e),
+    };
+
+    let updated_since = query.updated_since();
+
+    database
+        .run(move |connection| {
+            listed_companies(tenant.jobboard_id(), updated_since)
+                .paginate(company::company_id, pagination)
+                .load_page(connection)
+        })
+        .await
+        .into_page_response(Status::Ok)
 }
 
 #[openapi(tag = "Company")]
 #[post("/company", data = "<new_company>")]
-pub async fn add_new_company(new_company: Json<NewCompany>, database: Database) -> Response<Company> {
+pub async fn add_new_company(
+    new_company: Validated<NewCompany>,
+    tenant: Tenant,
+    database: Database,
+) -> Response<Company> {
     database
-        .create(company_table, new_company.into_inner())
+        .create(
+            company_table,
+            (company::jobboard_id.eq(tenant.jobboard_id()), new_company.into_inner()),
+        )
         .await
-        .into_response(Status::Created)
+        .into_versioned_response(Status::Created)
 }
 
 #[openapi(tag = "Company")]
 #[get("/company/<company_id>")]
-pub async fn get_company(company_id: i64, database: Database) -> Response<Company> {
-    database.get(company_table, company_id).await.into_response(Status::Ok)
+pub async fn get_company(company_id: i64, tenant: Tenant, database: Database) -> Response<Company> {
+    database
+        .get(tenant_companies(tenant), company_id)
+        .await
+        .into_versioned_response(Status::Ok)
 }
 
 #[openapi(tag = "Company")]
 #[put("/company/<company_id>", data = "<company_changeset>")]
 pub async fn update_company(
     company_id: i64,
-    company_changeset: Json<CompanyChangeset>,
+    company_changeset: Validated<CompanyChangeset>,
+    _admin: Admin,
+    if_match: IfMatch,
     database: Database,
 ) -> Response<Company> {
     database
-        .update(company_table, company_id, company_changeset.into_inner())
+        .update(
+            live_companies(),
+            company_id,
+            if_match.version(),
+            company_changeset.into_inner(),
+        )
         .await
-        .into_response(Status::Ok)
+        .into_versioned_response(Status::Ok)
+}
+
+#[openapi(tag = "Company")]
+#[patch("/company/<company_id>", data = "<company_patch>")]
+pub async fn patch_company(
+    company_id: i64,
+    company_patch: Validated<CompanyPatch>,
+    caller: Caller,
+    if_match: IfMatch,
+    database: Database,
+) -> Response<Company> {
+    let company_patch = company_patch.into_inner();
+
+    match caller {
+        Caller::Admin(_) => {
+            database
+                .update(live_companies(), company_id, if_match.version(), company_patch)
+                .await
+        }
+        Caller::Tenant(_) if company_patch.moderates() => return Response::Failure(moderation_forbidden()),
+        Caller::Tenant(tenant) => {
+            database
+                .update(tenant_companies(tenant), company_id, if_match.version(), company_patch)
+                .await
+        }
+    }
+    .into_versioned_response(Status::Ok)
 }
 
+/// Deletes the company, which fails with a conflict while it still has vacancies or applications unless `cascade` is
+/// set, in which case they get deleted along with it.
 #[openapi(tag = "Company")]
-#[delete("/company/<company_id>")]
-pub async fn delete_company(company_id: i64, database: Database) -> Response<()> {
+#[delete("/company/<company_id>?<cascade>")]
+pub async fn delete_company(
+    company_id: i64,
+    cascade: Option<bool>,
+    tenant: Tenant,
+    if_match: IfMatch,
+    database: Database,
+) -> Response<()> {
     database
-        .delete(company_table, company_id)
+        .run(move |connection| {
+            connection.transaction::<_, Error, _>(|| {
+                let company = diesel::update(tenant_companies(tenant).find(company_id))
+                    .set(company::deleted_at.eq(now))
+                    .get_result::<Company>(connection)?;
+
+                check_bumped_version(if_match.version(), company.version())?;
+
+                if cascade.unwrap_or(false) {
+                    delete_company_dependents(connection, company_id)?;
+                } else {
+                    Dependents::of_company(connection, company_id)?
+                        .ensure_none(&format!("Company '{}'", company_id))?;
+                }
+
+                Ok(())
+            })
+        })
         .await
         .into_response(Status::NoContent)
 }
+
+#[openapi(tag = "Company")]
+#[post("/company/<company_id>/restore")]
+pub async fn restore_company(company_id: i64, tenant: Tenant, database: Database) -> Response<Company> {
+    database
+        .restore(
+            company_table
+                .filter(company::jobboard_id.eq(tenant.jobboard_id()))
+                .filter(company::deleted_at.is_not_null()),
+            company_id,
+            company::deleted_at.eq(None::<DateTime<Utc>>),
+        )
+        .await
+        .into_versioned_response(Status::Ok)
+}
+
+#[openapi(tag = "Company")]
+#[get("/jobboard/<jobboard_id>/company?<query..>")]
+pub async fn get_jobboard_companies(
+    jobboard_id: i64,
+    query: ListQuery,
+    caller: Caller,
+    database: Database,
+) -> Response<Vec<Company>> {
+    if !caller.can_access(jobboard_id) {
+        return Response::Failure(Error::NotFound);
+    }
+
+    let pagination = match query.pagination(COMPANY_SORTABLE_COLUMNS) {
+        Ok(pagination) => pagination,
+        Err(e) => return Response::Failure(e),
+    };
+
+    let updated_since = query.updated_since();
+
+    database
+        .run(move |connection| {
+            listed_companies(jobboard_id, updated_since)
+                .paginate(company::company_id, pagination)
+                .load_page(connection)
+        })
+        .await
+        .into_page_response(Status::Ok)
+}
+
+#[openapi(tag = "Company")]
+#[post("/jobboard/<jobboard_id>/company", data = "<new_company>")]
+pub async fn add_new_jobboard_company(
+    jobboard_id: i64,
+    new_company: Validated<NewCompany>,
+    tenant: Tenant,
+    database: Database,
+) -> Response<Company> {
+    if jobboard_id != tenant.jobboard_id() {
+        return Response::Failure(Error::NotFound);
+    }
+
+    database
+        .create(
+            company_table,
+            (company::jobboard_id.eq(jobboard_id), new_company.into_inner()),
+        )
+        .await
+        .into_versioned_response(Status::Created)
+}
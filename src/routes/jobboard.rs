@@ -1,15 +1,25 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
+use diesel::dsl::{now, Eq, Filter, IsNull};
+use diesel::pg::Pg;
+use diesel::result::Error as DieselError;
+use diesel::{Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
 use rocket::http::Status;
-use rocket::serde::json::Json;
-use rocket::{delete, get, post, put};
+use rocket::{delete, get, patch, post, put};
 use rocket_okapi::openapi;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::response::IntoResponse;
+use super::cascade::{delete_jobboard_dependents, Dependents};
+use crate::auth;
+use crate::form::Timestamp;
+use crate::pagination::{ListQuery, Paginate};
+use crate::partial::{self, moderation_forbidden};
+use crate::response::{IntoPageResponse, IntoResponse, IntoVersionedResponse};
 use crate::schema::jobboard;
 use crate::schema::jobboard::dsl::jobboard as jobboard_table;
-use crate::{Database, Response};
+use crate::validation::{Validate, Validator};
+use crate::version::{check_bumped_version, Versioned};
+use crate::{Admin, Caller, Database, Error, IfMatch, Response, Tenant, Validated};
 
 #[derive(JsonSchema, Queryable, Serialize)]
 pub struct Jobboard {
@@ -17,10 +27,23 @@ pub struct Jobboard {
     jobboard_name: String,
     url: Option<String>,
     account: String,
-    key: Option<String>,
     timestamp: Option<DateTime<Utc>>,
     verified: bool,
     active: bool,
+    version: i32,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    deleted_at: Option<DateTime<Utc>>,
+}
+
+impl Versioned for Jobboard {
+    fn version(&self) -> i32 {
+        self.version
+    }
+
+    fn last_modified(&self) -> Option<DateTime<Utc>> {
+        Some(self.updated_at)
+    }
 }
 
 #[derive(JsonSchema, Deserialize, Insertable)]
@@ -30,7 +53,43 @@ pub struct NewJobboard {
     jobboard_name: String,
     url: Option<String>,
     account: String,
-    key: Option<String>,
+}
+
+impl Validate for NewJobboard {
+    fn validate(&self, validator: &mut Validator) {
+        validator
+            .required("jobboard_name", &self.jobboard_name)
+            .max_length("jobboard_name", &self.jobboard_name, 255)
+            .max_length("url", &self.url, 255)
+            .url("url", &self.url)
+            .required("account", &self.account)
+            .max_length("account", &self.account, 255);
+    }
+}
+
+#[derive(JsonSchema, Serialize)]
+pub struct CreatedJobboard {
+    #[serde(flatten)]
+    jobboard: Jobboard,
+    /// API key of the jobboard, which is only shown once.
+    key: String,
+}
+
+impl Versioned for CreatedJobboard {
+    fn version(&self) -> i32 {
+        self.jobboard.version()
+    }
+
+    fn last_modified(&self) -> Option<DateTime<Utc>> {
+        self.jobboard.last_modified()
+    }
+}
+
+#[derive(JsonSchema, Serialize)]
+pub struct JobboardKey {
+    /// New API key of the jobboard, which is only shown once.
+    key: String,
+    previous_keys_expire_at: DateTime<Utc>,
 }
 
 #[derive(JsonSchema, Deserialize, AsChangeset)]
@@ -41,48 +100,282 @@ pub struct JobboardChangeset {
     active: bool,
 }
 
+impl Validate for JobboardChangeset {}
+
+/// Partial update of a jobboard: missing fields are left untouched and nullable fields are cleared when set to `null`.
+///
+/// Only administrators can change the `verified` and `active` flags.
+#[derive(JsonSchema, Deserialize, AsChangeset)]
+#[serde(deny_unknown_fields)]
+#[table_name = "jobboard"]
+pub struct JobboardPatch {
+    #[serde(default, deserialize_with = "partial::non_null")]
+    jobboard_name: Option<String>,
+    #[serde(default, deserialize_with = "partial::nullable")]
+    url: Option<Option<String>>,
+    #[serde(default, deserialize_with = "partial::non_null")]
+    account: Option<String>,
+    #[serde(default, deserialize_with = "partial::non_null")]
+    verified: Option<bool>,
+    #[serde(default, deserialize_with = "partial::non_null")]
+    active: Option<bool>,
+}
+
+impl Validate for JobboardPatch {
+    fn validate(&self, validator: &mut Validator) {
+        validator
+            .not_empty(self.is_empty())
+            .required("jobboard_name", &self.jobboard_name)
+            .max_length("jobboard_name", &self.jobboard_name, 255)
+            .max_length("url", &self.url, 255)
+            .url("url", &self.url)
+            .required("account", &self.account)
+            .max_length("account", &self.account, 255);
+    }
+}
+
+impl JobboardPatch {
+    fn is_empty(&self) -> bool {
+        self.jobboard_name.is_none() && self.url.is_none() && self.account.is_none() && !self.moderates()
+    }
+
+    fn moderates(&self) -> bool {
+        self.verified.is_some() || self.active.is_some()
+    }
+}
+
+const JOBBOARD_SORTABLE_COLUMNS: &[&str] = &[
+    "jobboard_id",
+    "jobboard_name",
+    "timestamp",
+    "verified",
+    "active",
+    "created_at",
+    "updated_at",
+];
+
+const DEFAULT_KEY_GRACE_PERIOD: i64 = 24 * 60 * 60;
+const MAX_KEY_GRACE_PERIOD: i64 = 30 * 24 * 60 * 60;
+
+pub(crate) type LiveJobboards = Filter<jobboard_table, IsNull<jobboard::deleted_at>>;
+
+/// Restricts the jobboard table to the rows which are not soft deleted.
+pub(crate) fn live_jobboards() -> LiveJobboards {
+    jobboard_table.filter(jobboard::deleted_at.is_null())
+}
+
+type TenantJobboards = Filter<LiveJobboards, Eq<jobboard::jobboard_id, i64>>;
+
+/// Restricts the jobboard table to the tenant's own jobboard.
+fn tenant_jobboards(tenant: Tenant) -> TenantJobboards {
+    live_jobboards().filter(jobboard::jobboard_id.eq(tenant.jobboard_id()))
+}
+
 #[openapi(tag = "Jobboard")]
-#[get("/jobboard")]
-pub async fn get_all_jobboards(database: Database) -> Response<Vec<Jobboard>> {
-    database.get_all(jobboard_table).await.into_response(Status::Ok)
+#[get("/jobboard?<query..>")]
+pub async fn get_all_jobboards(query: ListQuery, caller: Caller, database: Database) -> Response<Vec<Jobboard>> {
+    let pagination = match query.pagination(JOBBOARD_SORTABLE_COLUMNS) {
+        Ok(pagination) => pagination,
+        Err(e) => return Response::Failure(e),
+    };
+
+    let updated_since = query.updated_since();
+
+    database
+        .run(move |connection| {
+            let mut query: jobboard::BoxedQuery<'static, Pg> = match (caller, updated_since) {
+                (Caller::Admin(_), Some(_)) => jobboard_table.into_boxed(),
+                (Caller::Admin(_), None) => live_jobboards().into_boxed(),
+                (Caller::Tenant(tenant), _) => tenant_jobboards(tenant).into_boxed(),
+            };
+            if let Some(Timestamp(updated_since)) = updated_since {
+                query = query.filter(jobboard::updated_at.ge(updated_since));
+            }
+
+            query.paginate(jobboard::jobboard_id, pagination).load_page(connection)
+        })
+        .await
+        .into_page_response(Status::Ok)
 }
 
 #[openapi(tag = "Jobboard")]
 #[post("/jobboard", data = "<new_jobboard>")]
-pub async fn add_new_jobboard(new_jobboard: Json<NewJobboard>, database: Database) -> Response<Jobboard> {
+pub async fn add_new_jobboard(new_jobboard: Validated<NewJobboard>, database: Database) -> Response<CreatedJobboard> {
+    let new_jobboard = new_jobboard.into_inner();
+
     database
-        .create(jobboard_table, new_jobboard.into_inner())
+        .run(move |connection| {
+            connection.transaction::<_, DieselError, _>(|| {
+                let jobboard: Jobboard = diesel::insert_into(jobboard_table)
+                    .values(new_jobboard)
+                    .get_result(connection)?;
+                let key = auth::issue_key(connection, jobboard.jobboard_id, Utc::now())?;
+
+                Ok(CreatedJobboard {
+                    jobboard,
+                    key: key.to_string(),
+                })
+            })
+        })
         .await
-        .into_response(Status::Created)
+        .into_versioned_response(Status::Created)
 }
 
 #[openapi(tag = "Jobboard")]
 #[get("/jobboard/<jobboard_id>")]
-pub async fn get_jobboard(jobboard_id: i64, database: Database) -> Response<Jobboard> {
+pub async fn get_jobboard(jobboard_id: i64, caller: Caller, database: Database) -> Response<Jobboard> {
+    if !caller.can_access(jobboard_id) {
+        return Response::Failure(Error::NotFound);
+    }
+
     database
-        .get(jobboard_table, jobboard_id)
+        .get(live_jobboards(), jobboard_id)
         .await
-        .into_response(Status::Ok)
+        .into_versioned_response(Status::Ok)
 }
 
 #[openapi(tag = "Jobboard")]
 #[put("/jobboard/<jobboard_id>", data = "<jobboard_changeset>")]
 pub async fn update_jobboard(
     jobboard_id: i64,
-    jobboard_changeset: Json<JobboardChangeset>,
+    jobboard_changeset: Validated<JobboardChangeset>,
+    _admin: Admin,
+    if_match: IfMatch,
     database: Database,
 ) -> Response<Jobboard> {
     database
-        .update(jobboard_table, jobboard_id, jobboard_changeset.into_inner())
+        .update(
+            live_jobboards(),
+            jobboard_id,
+            if_match.version(),
+            jobboard_changeset.into_inner(),
+        )
         .await
-        .into_response(Status::Ok)
+        .into_versioned_response(Status::Ok)
+}
+
+#[openapi(tag = "Jobboard")]
+#[patch("/jobboard/<jobboard_id>", data = "<jobboard_patch>")]
+pub async fn patch_jobboard(
+    jobboard_id: i64,
+    jobboard_patch: Validated<JobboardPatch>,
+    caller: Caller,
+    if_match: IfMatch,
+    database: Database,
+) -> Response<Jobboard> {
+    let jobboard_patch = jobboard_patch.into_inner();
+
+    match caller {
+        Caller::Admin(_) => {
+            database
+                .update(live_jobboards(), jobboard_id, if_match.version(), jobboard_patch)
+                .await
+        }
+        Caller::Tenant(_) if jobboard_patch.moderates() => return Response::Failure(moderation_forbidden()),
+        Caller::Tenant(tenant) => {
+            database
+                .update(
+                    tenant_jobboards(tenant),
+                    jobboard_id,
+                    if_match.version(),
+                    jobboard_patch,
+                )
+                .await
+        }
+    }
+    .into_versioned_response(Status::Ok)
 }
 
+/// Deletes the jobboard, which fails with a conflict while it still has companies, vacancies or applications unless
+/// `cascade` is set, in which case they get deleted along with it.
 #[openapi(tag = "Jobboard")]
-#[delete("/jobboard/<jobboard_id>")]
-pub async fn delete_jobboard(jobboard_id: i64, database: Database) -> Response<()> {
+#[delete("/jobboard/<jobboard_id>?<cascade>")]
+pub async fn delete_jobboard(
+    jobboard_id: i64,
+    cascade: Option<bool>,
+    _admin: Admin,
+    if_match: IfMatch,
+    database: Database,
+) -> Response<()> {
     database
-        .delete(jobboard_table, jobboard_id)
+        .run(move |connection| {
+            connection.transaction::<_, Error, _>(|| {
+                let jobboard = diesel::update(live_jobboards().find(jobboard_id))
+                    .set(jobboard::deleted_at.eq(now))
+                    .get_result::<Jobboard>(connection)?;
+
+                check_bumped_version(if_match.version(), jobboard.version())?;
+
+                if cascade.unwrap_or(false) {
+                    delete_jobboard_dependents(connection, jobboard_id)?;
+                } else {
+                    Dependents::of_jobboard(connection, jobboard_id)?
+                        .ensure_none(&format!("Jobboard '{}'", jobboard_id))?;
+                }
+
+                Ok(())
+            })
+        })
         .await
         .into_response(Status::NoContent)
 }
+
+#[openapi(tag = "Jobboard")]
+#[post("/jobboard/<jobboard_id>/restore")]
+pub async fn restore_jobboard(jobboard_id: i64, _admin: Admin, database: Database) -> Response<Jobboard> {
+    database
+        .restore(
+            jobboard_table.filter(jobboard::deleted_at.is_not_null()),
+            jobboard_id,
+            jobboard::deleted_at.eq(None::<DateTime<Utc>>),
+        )
+        .await
+        .into_versioned_response(Status::Ok)
+}
+
+/// Issues a new API key to the jobboard.
+///
+/// Previously issued keys remain valid for `grace_period` seconds, one day by default.
+#[openapi(tag = "Jobboard")]
+#[post("/jobboard/<jobboard_id>/key?<grace_period>")]
+pub async fn rotate_jobboard_key(
+    jobboard_id: i64,
+    grace_period: Option<i64>,
+    caller: Caller,
+    database: Database,
+) -> Response<JobboardKey> {
+    if !caller.can_access(jobboard_id) {
+        return Response::Failure(Error::NotFound);
+    }
+
+    let grace_period = grace_period.unwrap_or(DEFAULT_KEY_GRACE_PERIOD);
+
+    if !(0..=MAX_KEY_GRACE_PERIOD).contains(&grace_period) {
+        return Response::Failure(Error::BadRequest(format!(
+            "Grace period must be between 0 and {} seconds",
+            MAX_KEY_GRACE_PERIOD
+        )));
+    }
+
+    let previous_keys_expire_at = Utc::now() + Duration::seconds(grace_period);
+
+    database
+        .run(move |connection| {
+            connection.transaction::<_, DieselError, _>(|| {
+                // Locked so that the jobboard cannot be deleted before its key is issued.
+                live_jobboards()
+                    .find(jobboard_id)
+                    .select(jobboard::jobboard_id)
+                    .for_share()
+                    .first::<i64>(connection)?;
+
+                auth::issue_key(connection, jobboard_id, previous_keys_expire_at)
+            })
+        })
+        .await
+        .map(|key| JobboardKey {
+            key: key.to_string(),
+            previous_keys_expire_at,
+        })
+        .into_response(Status::Created)
+}
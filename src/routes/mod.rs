@@ -1,9 +1,12 @@
 mod application;
+mod cascade;
 mod company;
 mod jobboard;
+mod purge;
 mod vacancy;
 
 pub use application::*;
 pub use company::*;
 pub use jobboard::*;
+pub use purge::*;
 pub use vacancy::*;
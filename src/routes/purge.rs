@@ -0,0 +1,87 @@
+use chrono::{Duration, Utc};
+use diesel::{Connection as _, ExpressionMethods, QueryDsl, RunQueryDsl};
+use rocket::http::Status;
+use rocket::post;
+use rocket_okapi::openapi;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::response::IntoResponse;
+use crate::schema::application::dsl::application as application_table;
+use crate::schema::company::dsl::company as company_table;
+use crate::schema::jobboard::dsl::jobboard as jobboard_table;
+use crate::schema::vacancy::dsl::vacancy as vacancy_table;
+use crate::schema::{application, company, jobboard, vacancy};
+use crate::{Admin, Database, Error, Response};
+
+const DEFAULT_RETENTION: i64 = 30;
+const MAX_RETENTION: i64 = 3650;
+
+/// Number of soft deleted resources which got purged.
+#[derive(JsonSchema, Serialize)]
+pub struct PurgeReport {
+    jobboards: usize,
+    companies: usize,
+    vacancies: usize,
+    applications: usize,
+}
+
+/// Hard deletes the resources soft deleted more than `retention` days ago and no longer referenced by any other.
+#[openapi(tag = "Purge")]
+#[post("/purge?<retention>")]
+pub async fn purge_deleted(retention: Option<i64>, _admin: Admin, database: Database) -> Response<PurgeReport> {
+    let deleted_before = match retention.unwrap_or(DEFAULT_RETENTION) {
+        retention @ 1..=MAX_RETENTION => Utc::now().checked_sub_signed(Duration::days(retention)),
+        _ => None,
+    };
+    let deleted_before = match deleted_before {
+        Some(deleted_before) => deleted_before,
+        None => {
+            return Response::Failure(Error::BadRequest(format!(
+                "Retention must be between 1 and {} days",
+                MAX_RETENTION
+            )))
+        }
+    };
+
+    // Resources are purged from the most dependent ones onwards so that parents can go along with their children.
+    database
+        .run(move |connection| {
+            connection.transaction(|| {
+                let applications = diesel::delete(application_table.filter(application::deleted_at.lt(deleted_before)))
+                    .execute(connection)?;
+
+                let vacancies = diesel::delete(
+                    vacancy_table
+                        .filter(vacancy::deleted_at.lt(deleted_before))
+                        .filter(vacancy::vacancy_id.ne_all(application_table.select(application::vacancy_id))),
+                )
+                .execute(connection)?;
+
+                let companies = diesel::delete(
+                    company_table
+                        .filter(company::deleted_at.lt(deleted_before))
+                        .filter(company::company_id.ne_all(vacancy_table.select(vacancy::company_id))),
+                )
+                .execute(connection)?;
+
+                let jobboards = diesel::delete(
+                    jobboard_table
+                        .filter(jobboard::deleted_at.lt(deleted_before))
+                        .filter(jobboard::jobboard_id.ne_all(company_table.select(company::jobboard_id)))
+                        .filter(jobboard::jobboard_id.ne_all(vacancy_table.select(vacancy::jobboard_id)))
+                        .filter(jobboard::jobboard_id.ne_all(application_table.select(application::jobboard_id))),
+                )
+                .execute(connection)?;
+
+                Ok::<_, diesel::result::Error>(PurgeReport {
+                    jobboards,
+                    companies,
+                    vacancies,
+                    applications,
+                })
+            })
+        })
+        .await
+        .into_response(Status::Ok)
+}
@@ -1,19 +1,44 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
 use std::ops::Bound;
 
 use chrono::{DateTime, Utc};
+use diesel::deserialize::{self, FromSql};
+use diesel::dsl::{now, Eq, Filter, IsNull};
+use diesel::pg::Pg;
+use diesel::result::QueryResult;
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::{BigInt, Float, Int4range, Text};
+use diesel::{
+    BoolExpressionMethods, Connection as _, ExpressionMethods, IntoSql, PgTextExpressionMethods, QueryDsl, RunQueryDsl,
+};
 use rocket::http::Status;
-use rocket::serde::json::Json;
-use rocket::{delete, get, post, put};
+use rocket::{delete, get, patch, post, put, FromForm, FromFormField};
 use rocket_okapi::openapi;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::response::IntoResponse;
-use crate::schema::vacancy;
+use super::application::ApplicationStatus;
+use super::company::{tenant_companies, Company};
+use super::jobboard::Jobboard;
+use crate::database::Connection;
+use crate::form::Timestamp;
+use crate::include::Include;
+use crate::pagination::{ListQuery, Paginate, Pagination};
+use crate::partial::{self, moderation_forbidden};
+use crate::response::{IntoPageResponse, IntoResponse, IntoVersionedResponse};
+use crate::schema::application::dsl::application as application_table;
+use crate::schema::company::dsl::company as company_table;
+use crate::schema::jobboard::dsl::jobboard as jobboard_table;
 use crate::schema::vacancy::dsl::vacancy as vacancy_table;
-use crate::{Database, Response};
+use crate::schema::{application, company, jobboard, vacancy};
+use crate::validation::{Field, FieldError, Validate, Validator};
+use crate::version::Versioned;
+use crate::{Admin, Caller, Database, Error, IfMatch, Response, Tenant, Validated};
 
-#[derive(JsonSchema, Queryable, Serialize)]
+#[derive(JsonSchema, Queryable, QueryableByName, Serialize)]
+#[table_name = "vacancy"]
 pub struct Vacancy {
     vacancy_id: i64,
     jobboard_id: i64,
@@ -30,17 +55,120 @@ pub struct Vacancy {
     description: Option<String>,
     url: Option<String>,
     commission: Option<i16>,
-    status: String,
+    status: VacancyStatus,
     verified: bool,
     active: bool,
+    publish_at: Option<DateTime<Utc>>,
+    expires_at: Option<DateTime<Utc>>,
+    version: i32,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    deleted_at: Option<DateTime<Utc>>,
+}
+
+impl Versioned for Vacancy {
+    fn version(&self) -> i32 {
+        self.version
+    }
+
+    fn last_modified(&self) -> Option<DateTime<Utc>> {
+        Some(self.updated_at)
+    }
+}
+
+/// Stage of a vacancy in its lifecycle.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, JsonSchema, Deserialize, Serialize, AsExpression, FromSqlRow, FromFormField,
+)]
+#[serde(rename_all = "snake_case")]
+#[sql_type = "Text"]
+pub enum VacancyStatus {
+    Draft,
+    Published,
+    Paused,
+    Filled,
+    Closed,
+    Expired,
+}
+
+impl VacancyStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Draft => "draft",
+            Self::Published => "published",
+            Self::Paused => "paused",
+            Self::Filled => "filled",
+            Self::Closed => "closed",
+            Self::Expired => "expired",
+        }
+    }
+
+    fn can_transition_to(self, next: Self) -> bool {
+        use VacancyStatus::*;
+
+        matches!(
+            (self, next),
+            (Draft | Paused, Published)
+                | (Published, Paused)
+                | (Published | Paused, Filled | Expired)
+                | (Draft | Published | Paused | Filled | Expired, Closed)
+        )
+    }
+}
+
+impl fmt::Display for VacancyStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl ToSql<Text, Pg> for VacancyStatus {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Pg>) -> serialize::Result {
+        <str as ToSql<Text, Pg>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Pg> for VacancyStatus {
+    fn from_sql(bytes: Option<&[u8]>) -> deserialize::Result<Self> {
+        match <String as FromSql<Text, Pg>>::from_sql(bytes)?.as_str() {
+            "draft" => Ok(Self::Draft),
+            "published" => Ok(Self::Published),
+            "paused" => Ok(Self::Paused),
+            "filled" => Ok(Self::Filled),
+            "closed" => Ok(Self::Closed),
+            "expired" => Ok(Self::Expired),
+            status => Err(format!("Unknown vacancy status '{}'", status).into()),
+        }
+    }
+}
+
+/// Vacancy alongside the related resources requested through the `include` query parameter.
+#[derive(JsonSchema, Serialize)]
+pub struct VacancyWithRelations {
+    #[serde(flatten)]
+    vacancy: Vacancy,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    company: Option<Company>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    jobboard: Option<Jobboard>,
+}
+
+/// Versioned as the vacancy itself, which only identifies responses embedding no relation.
+impl Versioned for VacancyWithRelations {
+    fn version(&self) -> i32 {
+        self.vacancy.version()
+    }
+
+    fn last_modified(&self) -> Option<DateTime<Utc>> {
+        self.vacancy.last_modified()
+    }
 }
 
 #[derive(JsonSchema, Deserialize, Insertable)]
 #[serde(deny_unknown_fields)]
 #[table_name = "vacancy"]
 pub struct NewVacancy {
-    jobboard_id: i64,
-    vacancy_id: i64,
+    company_id: i64,
     job_title: String,
     location: Option<String>,
     start_date: Option<DateTime<Utc>>,
@@ -53,56 +181,888 @@ pub struct NewVacancy {
     description: Option<String>,
     url: Option<String>,
     commission: Option<i16>,
+    /// When the vacancy gets published, provided it is still a draft and is active and verified by then.
+    publish_at: Option<DateTime<Utc>>,
+    /// When the vacancy gets expired, which must be after `publish_at`.
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl Validate for NewVacancy {
+    fn validate(&self, validator: &mut Validator) {
+        validate_vacancy(
+            validator,
+            &self.job_title,
+            &self.location,
+            &self.responsibilities,
+            &self.skills,
+            &self.conditions,
+            &self.description,
+            &self.url,
+            &self.commission,
+            &self.publish_at,
+            &self.expires_at,
+        );
+    }
+}
+
+/// Vacancy created through its company, the company being given by the URL.
+#[derive(JsonSchema, Deserialize, Insertable)]
+#[serde(deny_unknown_fields)]
+#[table_name = "vacancy"]
+pub struct NewCompanyVacancy {
+    job_title: String,
+    location: Option<String>,
+    start_date: Option<DateTime<Utc>>,
+    directly: Option<bool>,
+    hours: (Bound<i32>, Bound<i32>),
+    positions: Option<i16>,
+    responsibilities: Option<String>,
+    skills: Option<String>,
+    conditions: Option<String>,
+    description: Option<String>,
+    url: Option<String>,
+    commission: Option<i16>,
+    /// When the vacancy gets published, provided it is still a draft and is active and verified by then.
+    publish_at: Option<DateTime<Utc>>,
+    /// When the vacancy gets expired, which must be after `publish_at`.
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl Validate for NewCompanyVacancy {
+    fn validate(&self, validator: &mut Validator) {
+        validate_vacancy(
+            validator,
+            &self.job_title,
+            &self.location,
+            &self.responsibilities,
+            &self.skills,
+            &self.conditions,
+            &self.description,
+            &self.url,
+            &self.commission,
+            &self.publish_at,
+            &self.expires_at,
+        );
+    }
 }
 
 #[derive(JsonSchema, Deserialize, AsChangeset)]
 #[serde(deny_unknown_fields)]
 #[table_name = "vacancy"]
 pub struct VacancyChangeset {
-    status: String,
     verified: bool,
     active: bool,
 }
 
+impl Validate for VacancyChangeset {}
+
+/// Partial update of a vacancy: missing fields are left untouched and nullable fields are cleared when set to `null`.
+///
+/// Only administrators can change the `verified` and `active` flags, the status going through the lifecycle routes.
+#[derive(JsonSchema, Deserialize, AsChangeset)]
+#[serde(deny_unknown_fields)]
+#[table_name = "vacancy"]
+pub struct VacancyPatch {
+    #[serde(default, deserialize_with = "partial::non_null")]
+    job_title: Option<String>,
+    #[serde(default, deserialize_with = "partial::nullable")]
+    location: Option<Option<String>>,
+    #[serde(default, deserialize_with = "partial::nullable")]
+    start_date: Option<Option<DateTime<Utc>>>,
+    #[serde(default, deserialize_with = "partial::nullable")]
+    directly: Option<Option<bool>>,
+    #[serde(default, deserialize_with = "partial::non_null")]
+    hours: Option<(Bound<i32>, Bound<i32>)>,
+    #[serde(default, deserialize_with = "partial::nullable")]
+    positions: Option<Option<i16>>,
+    #[serde(default, deserialize_with = "partial::nullable")]
+    responsibilities: Option<Option<String>>,
+    #[serde(default, deserialize_with = "partial::nullable")]
+    skills: Option<Option<String>>,
+    #[serde(default, deserialize_with = "partial::nullable")]
+    conditions: Option<Option<String>>,
+    #[serde(default, deserialize_with = "partial::nullable")]
+    description: Option<Option<String>>,
+    #[serde(default, deserialize_with = "partial::nullable")]
+    url: Option<Option<String>>,
+    #[serde(default, deserialize_with = "partial::nullable")]
+    commission: Option<Option<i16>>,
+    #[serde(default, deserialize_with = "partial::nullable")]
+    publish_at: Option<Option<DateTime<Utc>>>,
+    #[serde(default, deserialize_with = "partial::nullable")]
+    expires_at: Option<Option<DateTime<Utc>>>,
+    #[serde(default, deserialize_with = "partial::non_null")]
+    verified: Option<bool>,
+    #[serde(default, deserialize_with = "partial::non_null")]
+    active: Option<bool>,
+}
+
+impl Validate for VacancyPatch {
+    fn validate(&self, validator: &mut Validator) {
+        validate_vacancy(
+            validator.not_empty(self.is_empty()),
+            &self.job_title,
+            &self.location,
+            &self.responsibilities,
+            &self.skills,
+            &self.conditions,
+            &self.description,
+            &self.url,
+            &self.commission,
+            &self.publish_at,
+            &self.expires_at,
+        );
+    }
+}
+
+/// Checks the fields shared by the bodies creating and changing vacancies, which are only checked when set.
+#[allow(clippy::too_many_arguments)]
+fn validate_vacancy(
+    validator: &mut Validator,
+    job_title: &impl Field<str>,
+    location: &impl Field<str>,
+    responsibilities: &impl Field<str>,
+    skills: &impl Field<str>,
+    conditions: &impl Field<str>,
+    description: &impl Field<str>,
+    url: &impl Field<str>,
+    commission: &impl Field<i16>,
+    publish_at: &impl Field<DateTime<Utc>>,
+    expires_at: &impl Field<DateTime<Utc>>,
+) {
+    validator
+        .required("job_title", job_title)
+        .max_length("job_title", job_title, 255)
+        .max_length("location", location, 255)
+        .max_length("responsibilities", responsibilities, 1200)
+        .max_length("skills", skills, 1200)
+        .max_length("conditions", conditions, 1200)
+        .max_length("description", description, 2000)
+        .max_length("url", url, 255)
+        .url("url", url)
+        .max("commission", commission, 10000);
+
+    if let (Some(publish_at), Some(expires_at)) = (publish_at.value(), expires_at.value()) {
+        if expires_at <= publish_at {
+            validator.invalid("expires_at", "before_publish_at", "Must be after publish_at");
+        }
+    }
+}
+
+impl VacancyPatch {
+    fn is_empty(&self) -> bool {
+        self.job_title.is_none()
+            && self.location.is_none()
+            && self.start_date.is_none()
+            && self.directly.is_none()
+            && self.hours.is_none()
+            && self.positions.is_none()
+            && self.responsibilities.is_none()
+            && self.skills.is_none()
+            && self.conditions.is_none()
+            && self.description.is_none()
+            && self.url.is_none()
+            && self.commission.is_none()
+            && self.publish_at.is_none()
+            && self.expires_at.is_none()
+            && !self.moderates()
+    }
+
+    fn moderates(&self) -> bool {
+        self.verified.is_some() || self.active.is_some()
+    }
+}
+
+#[derive(JsonSchema, QueryableByName, Serialize)]
+pub struct VacancySearchResult {
+    #[diesel(embed)]
+    #[serde(flatten)]
+    vacancy: Vacancy,
+    /// Relevance of the vacancy, results being sorted from the most to the least relevant.
+    #[sql_type = "Float"]
+    rank: f32,
+    /// Excerpts of the vacancy matching the search, the matching words being enclosed in `<mark>` tags.
+    #[sql_type = "Text"]
+    snippet: String,
+}
+
+/// Criteria to filter vacancies with, every criterion being optional, along with the pagination of the listing.
+#[derive(FromForm, JsonSchema)]
+pub struct VacancyFilter {
+    limit: Option<i64>,
+    offset: Option<i64>,
+    /// Identifier of the last vacancy of the previous page, on listings ordered by identifier.
+    after: Option<i64>,
+    /// Comma-separated list of columns to sort by, in descending order when prefixed with `-`.
+    sort: Option<String>,
+    company_id: Option<i64>,
+    /// Case-insensitive part of the location.
+    location: Option<String>,
+    status: Option<VacancyStatus>,
+    active: Option<bool>,
+    verified: Option<bool>,
+    directly: Option<bool>,
+    /// Earliest start date, inclusive.
+    start_date_from: Option<Timestamp>,
+    /// Latest start date, inclusive.
+    start_date_to: Option<Timestamp>,
+    /// Lower bound of the weekly hours: only vacancies whose hours overlap `[min_hours, max_hours]` are kept.
+    min_hours: Option<i32>,
+    /// Upper bound of the weekly hours: only vacancies whose hours overlap `[min_hours, max_hours]` are kept.
+    max_hours: Option<i32>,
+    /// Only vacancies updated at or after this time, for incremental sync.
+    updated_since: Option<Timestamp>,
+}
+
+impl VacancyFilter {
+    fn pagination(&self) -> Result<Pagination, Error> {
+        Pagination::new(self.limit, self.offset, self.after).sorted_by(self.sort.as_deref(), VACANCY_SORTABLE_COLUMNS)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        match (self.min_hours, self.max_hours) {
+            (Some(min_hours), Some(max_hours)) if min_hours > max_hours => Err(Error::BadRequest(
+                "Minimum hours must be lower than or equal to maximum hours".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Vacancies of the tenant matching the filter.
+    fn apply(self, tenant: Tenant) -> vacancy::BoxedQuery<'static, Pg> {
+        let mut query = listed_vacancies(tenant, self.updated_since);
+
+        if let Some(company_id) = self.company_id {
+            query = query.filter(vacancy::company_id.eq(company_id));
+        }
+        if let Some(location) = self.location {
+            query = query.filter(vacancy::location.ilike(format!("%{}%", location)));
+        }
+        if let Some(status) = self.status {
+            query = query.filter(vacancy::status.eq(status));
+        }
+        if let Some(active) = self.active {
+            query = query.filter(vacancy::active.eq(active));
+        }
+        if let Some(verified) = self.verified {
+            query = query.filter(vacancy::verified.eq(verified));
+        }
+        if let Some(directly) = self.directly {
+            query = query.filter(vacancy::directly.eq(directly));
+        }
+        if let Some(Timestamp(start_date_from)) = self.start_date_from {
+            query = query.filter(vacancy::start_date.ge(start_date_from));
+        }
+        if let Some(Timestamp(start_date_to)) = self.start_date_to {
+            query = query.filter(vacancy::start_date.le(start_date_to));
+        }
+        if self.min_hours.is_some() || self.max_hours.is_some() {
+            let hours = (
+                self.min_hours.map_or(Bound::Unbounded, Bound::Included),
+                self.max_hours.map_or(Bound::Unbounded, Bound::Included),
+            );
+            query = query.filter(RangeOverlaps::new(vacancy::hours, hours.into_sql::<Int4range>()));
+        }
+
+        query
+    }
+}
+
+diesel_infix_operator!(RangeOverlaps, " && ", backend: Pg);
+
+const SEARCH_QUERY: &str = "\
+    SELECT vacancy.*, \
+        ts_rank(vacancy.search, query) AS rank, \
+        ts_headline('simple', \
+            concat_ws(' ', vacancy.job_title, vacancy.skills, vacancy.responsibilities, vacancy.description), \
+            query, 'StartSel=<mark>, StopSel=</mark>, MaxFragments=2, MinWords=5, MaxWords=20') AS snippet \
+    FROM vacancy \
+    CROSS JOIN websearch_to_tsquery('simple', $1) AS query \
+    WHERE vacancy.jobboard_id = $2 AND vacancy.search @@ query \
+        AND vacancy.status = 'published' AND vacancy.active AND vacancy.verified AND vacancy.deleted_at IS NULL \
+    ORDER BY rank DESC, vacancy.vacancy_id \
+    LIMIT $3 OFFSET $4";
+
+const VACANCY_SORTABLE_COLUMNS: &[&str] = &[
+    "vacancy_id",
+    "company_id",
+    "job_title",
+    "location",
+    "start_date",
+    "positions",
+    "commission",
+    "status",
+    "verified",
+    "active",
+    "created_at",
+    "updated_at",
+];
+
+const VACANCY_RELATIONS: &[&str] = &["company", "jobboard"];
+
+pub(crate) type LiveVacancies = Filter<vacancy_table, IsNull<vacancy::deleted_at>>;
+
+/// Restricts the vacancy table to the rows which are not soft deleted.
+pub(crate) fn live_vacancies() -> LiveVacancies {
+    vacancy_table.filter(vacancy::deleted_at.is_null())
+}
+
+pub(crate) type TenantVacancies = Filter<LiveVacancies, Eq<vacancy::jobboard_id, i64>>;
+
+/// Restricts the vacancy table to the live rows owned by the given tenant.
+pub(crate) fn tenant_vacancies(tenant: Tenant) -> TenantVacancies {
+    live_vacancies().filter(vacancy::jobboard_id.eq(tenant.jobboard_id()))
+}
+
+/// Vacancies of the tenant to list, the deleted ones included when syncing the changes made since `updated_since`.
+fn listed_vacancies(tenant: Tenant, updated_since: Option<Timestamp>) -> vacancy::BoxedQuery<'static, Pg> {
+    match updated_since {
+        Some(Timestamp(updated_since)) => vacancy_table
+            .filter(vacancy::jobboard_id.eq(tenant.jobboard_id()))
+            .filter(vacancy::updated_at.ge(updated_since))
+            .into_boxed(),
+        None => tenant_vacancies(tenant).into_boxed(),
+    }
+}
+
+/// Publishes the vacancies whose `publish_at` has passed and expires the ones whose `expires_at` has passed, returning
+/// the number of published and expired vacancies.
+///
+/// Only active and verified drafts get published, the other ones being left for a later run.
+pub(crate) fn apply_schedule(connection: &Connection) -> QueryResult<(usize, usize)> {
+    connection.transaction(|| {
+        let expired = diesel::update(
+            live_vacancies()
+                .filter(vacancy::status.eq_any(vec![VacancyStatus::Published, VacancyStatus::Paused]))
+                .filter(vacancy::expires_at.le(now)),
+        )
+        .set(vacancy::status.eq(VacancyStatus::Expired))
+        .execute(connection)?;
+
+        let published = diesel::update(
+            live_vacancies()
+                .filter(vacancy::status.eq(VacancyStatus::Draft))
+                .filter(vacancy::publish_at.le(now))
+                .filter(vacancy::expires_at.is_null().or(vacancy::expires_at.gt(now)))
+                .filter(vacancy::active.eq(true))
+                .filter(vacancy::verified.eq(true)),
+        )
+        .set(vacancy::status.eq(VacancyStatus::Published))
+        .execute(connection)?;
+
+        Ok((published, expired))
+    })
+}
+
+/// Marks the vacancy as filled once as many applications as its positions have been hired. Vacancies without
+/// positions are never filled automatically.
+///
+/// The vacancy is locked beforehand so that concurrent hirings are counted one after the other.
+pub(crate) fn fill_if_staffed(connection: &Connection, vacancy_id: i64) -> QueryResult<()> {
+    let (status, positions) = vacancy_table
+        .find(vacancy_id)
+        .select((vacancy::status, vacancy::positions))
+        .for_update()
+        .first::<(VacancyStatus, Option<i16>)>(connection)?;
+
+    let positions = match positions {
+        Some(positions) if status.can_transition_to(VacancyStatus::Filled) => positions,
+        _ => return Ok(()),
+    };
+
+    let hired = application_table
+        .filter(application::vacancy_id.eq(vacancy_id))
+        .filter(application::status.eq(ApplicationStatus::Hired))
+        .filter(application::deleted_at.is_null())
+        .count()
+        .get_result::<i64>(connection)?;
+
+    if hired >= i64::from(positions) {
+        diesel::update(vacancy_table.find(vacancy_id))
+            .set(vacancy::status.eq(VacancyStatus::Filled))
+            .execute(connection)?;
+    }
+
+    Ok(())
+}
+
+/// Embeds the related resources requested by `include` into the vacancies, each relation being loaded with a
+/// single join whatever the number of vacancies. Deleted resources are left out.
+fn embed_relations(
+    connection: &Connection,
+    vacancies: Vec<Vacancy>,
+    include: &Include,
+) -> QueryResult<Vec<VacancyWithRelations>> {
+    let vacancy_ids = vacancies.iter().map(|vacancy| vacancy.vacancy_id).collect::<Vec<_>>();
+
+    let mut companies = if include.contains("company") {
+        vacancy_table
+            .inner_join(company_table)
+            .filter(vacancy::vacancy_id.eq_any(&vacancy_ids))
+            .filter(company::deleted_at.is_null())
+            .select((vacancy::vacancy_id, company::all_columns))
+            .load::<(i64, Company)>(connection)?
+            .into_iter()
+            .collect::<HashMap<_, _>>()
+    } else {
+        HashMap::new()
+    };
+
+    let mut jobboards = if include.contains("jobboard") {
+        vacancy_table
+            .inner_join(jobboard_table)
+            .filter(vacancy::vacancy_id.eq_any(&vacancy_ids))
+            .filter(jobboard::deleted_at.is_null())
+            .select((vacancy::vacancy_id, jobboard::all_columns))
+            .load::<(i64, Jobboard)>(connection)?
+            .into_iter()
+            .collect::<HashMap<_, _>>()
+    } else {
+        HashMap::new()
+    };
+
+    Ok(vacancies
+        .into_iter()
+        .map(|vacancy| VacancyWithRelations {
+            company: companies.remove(&vacancy.vacancy_id),
+            jobboard: jobboards.remove(&vacancy.vacancy_id),
+            vacancy,
+        })
+        .collect())
+}
+
 #[openapi(tag = "Vacancy")]
-#[get("/vacancy")]
-pub async fn get_all_vacancies(database: Database) -> Response<Vec<Vacancy>> {
-    database.get_all(vacancy_table).await.into_response(Status::Ok)
+#[get("/vacancy?<include>&<filter..>")]
+pub async fn get_all_vacancies(
+    include: Option<String>,
+    filter: VacancyFilter,
+    tenant: Tenant,
+    database: Database,
+) -> Response<Vec<VacancyWithRelations>> {
+    if let Err(e) = filter.validate() {
+        return Response::Failure(e);
+    }
+
+    let pagination = match filter.pagination() {
+        Ok(pagination) => pagination,
+        Err(e) => return Response::Failure(e),
+    };
+
+    let include = match Include::parse(include.as_deref(), VACANCY_RELATIONS) {
+        Ok(include) => include,
+        Err(e) => return Response::Failure(e),
+    };
+
+    database
+        .run(move |connection| {
+            filter
+                .apply(tenant)
+                .paginate(vacancy::vacancy_id, pagination)
+                .load_page(connection)?
+                .try_map(|vacancies| embed_relations(connection, vacancies, &include))
+        })
+        .await
+        .into_page_response(Status::Ok)
 }
 
 #[openapi(tag = "Vacancy")]
 #[post("/vacancy", data = "<new_vacancy>")]
-pub async fn add_new_vacancy(new_vacancy: Json<NewVacancy>, database: Database) -> Response<Vacancy> {
+pub async fn add_new_vacancy(
+    new_vacancy: Validated<NewVacancy>,
+    tenant: Tenant,
+    database: Database,
+) -> Response<Vacancy> {
+    let new_vacancy = new_vacancy.into_inner();
+
+    let company = database
+        .get::<_, _, i64>(
+            tenant_companies(tenant).select(company::company_id),
+            new_vacancy.company_id,
+        )
+        .await;
+
+    match company {
+        Ok(_) => database
+            .create(
+                vacancy_table,
+                (vacancy::jobboard_id.eq(tenant.jobboard_id()), new_vacancy),
+            )
+            .await
+            .into_versioned_response(Status::Created),
+        Err(diesel::result::Error::NotFound) => Response::Failure(Error::InvalidFields(vec![FieldError::new(
+            "company_id",
+            "unknown_reference",
+            "Must refer to an existing company",
+        )])),
+        Err(e) => Response::Failure(e.into()),
+    }
+}
+
+/// Searches the published, active and verified vacancies matching the keywords of `q`, which supports quoted phrases,
+/// `or` and `-` exclusions.
+#[openapi(tag = "Vacancy")]
+#[get("/vacancy/search?<q>&<limit>&<offset>")]
+pub async fn search_vacancies(
+    q: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    tenant: Tenant,
+    database: Database,
+) -> Response<Vec<VacancySearchResult>> {
+    let q = match q {
+        Some(q) if !q.trim().is_empty() => q,
+        _ => return Response::Failure(Error::BadRequest("Search query must not be empty".to_string())),
+    };
+
+    let pagination = Pagination::new(limit, offset, None);
+
     database
-        .create(vacancy_table, new_vacancy.into_inner())
+        .run(move |connection| {
+            diesel::sql_query(SEARCH_QUERY)
+                .bind::<Text, _>(q)
+                .bind::<BigInt, _>(tenant.jobboard_id())
+                .bind::<BigInt, _>(pagination.limit())
+                .bind::<BigInt, _>(pagination.offset())
+                .load::<VacancySearchResult>(connection)
+        })
         .await
-        .into_response(Status::Created)
+        .into_response(Status::Ok)
 }
 
 #[openapi(tag = "Vacancy")]
-#[get("/vacancy/<vacancy_id>")]
-pub async fn get_vacancy(vacancy_id: i64, database: Database) -> Response<Vacancy> {
-    database.get(vacancy_table, vacancy_id).await.into_response(Status::Ok)
+#[get("/vacancy/<vacancy_id>?<include>")]
+pub async fn get_vacancy(
+    vacancy_id: i64,
+    include: Option<String>,
+    tenant: Tenant,
+    database: Database,
+) -> Response<VacancyWithRelations> {
+    let include = match Include::parse(include.as_deref(), VACANCY_RELATIONS) {
+        Ok(include) => include,
+        Err(e) => return Response::Failure(e),
+    };
+
+    // Embedded resources change independently of the vacancy, whose version then no longer identifies the response.
+    let versioned = include.is_empty();
+    let vacancy = database
+        .run(move |connection| {
+            let vacancy = tenant_vacancies(tenant).find(vacancy_id).first::<Vacancy>(connection)?;
+
+            embed_relations(connection, vec![vacancy], &include).map(|mut vacancies| vacancies.remove(0))
+        })
+        .await;
+
+    if versioned {
+        vacancy.into_versioned_response(Status::Ok)
+    } else {
+        vacancy.into_response(Status::Ok)
+    }
 }
 
 #[openapi(tag = "Vacancy")]
 #[put("/vacancy/<vacancy_id>", data = "<vacancy_changeset>")]
 pub async fn update_vacancy(
     vacancy_id: i64,
-    vacancy_changeset: Json<VacancyChangeset>,
+    vacancy_changeset: Validated<VacancyChangeset>,
+    _admin: Admin,
+    if_match: IfMatch,
     database: Database,
 ) -> Response<Vacancy> {
     database
-        .update(vacancy_table, vacancy_id, vacancy_changeset.into_inner())
+        .update(
+            live_vacancies(),
+            vacancy_id,
+            if_match.version(),
+            vacancy_changeset.into_inner(),
+        )
         .await
-        .into_response(Status::Ok)
+        .into_versioned_response(Status::Ok)
+}
+
+#[openapi(tag = "Vacancy")]
+#[patch("/vacancy/<vacancy_id>", data = "<vacancy_patch>")]
+pub async fn patch_vacancy(
+    vacancy_id: i64,
+    vacancy_patch: Validated<VacancyPatch>,
+    caller: Caller,
+    if_match: IfMatch,
+    database: Database,
+) -> Response<Vacancy> {
+    let vacancy_patch = vacancy_patch.into_inner();
+
+    match caller {
+        Caller::Admin(_) => {
+            database
+                .update(live_vacancies(), vacancy_id, if_match.version(), vacancy_patch)
+                .await
+        }
+        Caller::Tenant(_) if vacancy_patch.moderates() => return Response::Failure(moderation_forbidden()),
+        Caller::Tenant(tenant) => {
+            database
+                .update(tenant_vacancies(tenant), vacancy_id, if_match.version(), vacancy_patch)
+                .await
+        }
+    }
+    .into_versioned_response(Status::Ok)
+}
+
+#[openapi(tag = "Vacancy")]
+#[post("/vacancy/<vacancy_id>/publish")]
+pub async fn publish_vacancy(vacancy_id: i64, tenant: Tenant, database: Database) -> Response<Vacancy> {
+    change_status(vacancy_id, VacancyStatus::Published, tenant, database).await
+}
+
+#[openapi(tag = "Vacancy")]
+#[post("/vacancy/<vacancy_id>/pause")]
+pub async fn pause_vacancy(vacancy_id: i64, tenant: Tenant, database: Database) -> Response<Vacancy> {
+    change_status(vacancy_id, VacancyStatus::Paused, tenant, database).await
+}
+
+#[openapi(tag = "Vacancy")]
+#[post("/vacancy/<vacancy_id>/fill")]
+pub async fn fill_vacancy(vacancy_id: i64, tenant: Tenant, database: Database) -> Response<Vacancy> {
+    change_status(vacancy_id, VacancyStatus::Filled, tenant, database).await
+}
+
+#[openapi(tag = "Vacancy")]
+#[post("/vacancy/<vacancy_id>/close")]
+pub async fn close_vacancy(vacancy_id: i64, tenant: Tenant, database: Database) -> Response<Vacancy> {
+    change_status(vacancy_id, VacancyStatus::Closed, tenant, database).await
+}
+
+async fn change_status(vacancy_id: i64, next: VacancyStatus, tenant: Tenant, database: Database) -> Response<Vacancy> {
+    database
+        .run(move |connection| {
+            connection.transaction::<_, Error, _>(|| {
+                let (status, verified, active) = tenant_vacancies(tenant)
+                    .find(vacancy_id)
+                    .select((vacancy::status, vacancy::verified, vacancy::active))
+                    .for_update()
+                    .first::<(VacancyStatus, bool, bool)>(connection)?;
+
+                if !status.can_transition_to(next) {
+                    return Err(Error::InvalidData(format!(
+                        "Cannot move a vacancy from '{}' to '{}'",
+                        status, next
+                    )));
+                }
+                if next == VacancyStatus::Published && !(verified && active) {
+                    return Err(Error::InvalidData(
+                        "Only active and verified vacancies can be published".to_string(),
+                    ));
+                }
+
+                diesel::update(vacancy_table.find(vacancy_id))
+                    .set(vacancy::status.eq(next))
+                    .get_result::<Vacancy>(connection)
+                    .map_err(Into::into)
+            })
+        })
+        .await
+        .into_versioned_response(Status::Ok)
+}
+
+/// Lists the published, active and verified vacancies of an active and verified jobboard, which requires no API key.
+#[openapi(tag = "Vacancy")]
+#[get("/jobboard/<jobboard_id>/vacancy?<query..>")]
+pub async fn get_public_vacancies(jobboard_id: i64, query: ListQuery, database: Database) -> Response<Vec<Vacancy>> {
+    let pagination = match query.pagination(VACANCY_SORTABLE_COLUMNS) {
+        Ok(pagination) => pagination,
+        Err(e) => return Response::Failure(e),
+    };
+
+    let updated_since = query.updated_since();
+
+    database
+        .run(move |connection| {
+            let mut query = vacancy_table
+                .inner_join(jobboard_table)
+                .filter(vacancy::jobboard_id.eq(jobboard_id))
+                .filter(vacancy::deleted_at.is_null())
+                .filter(jobboard::deleted_at.is_null())
+                .filter(jobboard::active.eq(true))
+                .filter(jobboard::verified.eq(true))
+                .filter(vacancy::status.eq(VacancyStatus::Published))
+                .filter(vacancy::active.eq(true))
+                .filter(vacancy::verified.eq(true))
+                .select(vacancy::all_columns)
+                .into_boxed();
+            // Public listings never hold deleted vacancies, which are only synced by their tenant.
+            if let Some(Timestamp(updated_since)) = updated_since {
+                query = query.filter(vacancy::updated_at.ge(updated_since));
+            }
+
+            query.paginate(vacancy::vacancy_id, pagination).load_page(connection)
+        })
+        .await
+        .into_page_response(Status::Ok)
 }
 
 #[openapi(tag = "Vacancy")]
 #[delete("/vacancy/<vacancy_id>")]
-pub async fn delete_vacancy(vacancy_id: i64, database: Database) -> Response<()> {
+pub async fn delete_vacancy(vacancy_id: i64, tenant: Tenant, if_match: IfMatch, database: Database) -> Response<()> {
     database
-        .delete(vacancy_table, vacancy_id)
+        .delete::<_, _, _, Vacancy>(
+            tenant_vacancies(tenant),
+            vacancy_id,
+            if_match.version(),
+            vacancy::deleted_at.eq(now),
+        )
         .await
         .into_response(Status::NoContent)
 }
+
+#[openapi(tag = "Vacancy")]
+#[post("/vacancy/<vacancy_id>/restore")]
+pub async fn restore_vacancy(vacancy_id: i64, tenant: Tenant, database: Database) -> Response<Vacancy> {
+    database
+        .restore(
+            vacancy_table
+                .filter(vacancy::jobboard_id.eq(tenant.jobboard_id()))
+                .filter(vacancy::deleted_at.is_not_null()),
+            vacancy_id,
+            vacancy::deleted_at.eq(None::<DateTime<Utc>>),
+        )
+        .await
+        .into_versioned_response(Status::Ok)
+}
+
+#[openapi(tag = "Vacancy")]
+#[get("/company/<company_id>/vacancy?<include>&<query..>")]
+pub async fn get_company_vacancies(
+    company_id: i64,
+    include: Option<String>,
+    query: ListQuery,
+    tenant: Tenant,
+    database: Database,
+) -> Response<Vec<VacancyWithRelations>> {
+    let pagination = match query.pagination(VACANCY_SORTABLE_COLUMNS) {
+        Ok(pagination) => pagination,
+        Err(e) => return Response::Failure(e),
+    };
+
+    let include = match Include::parse(include.as_deref(), VACANCY_RELATIONS) {
+        Ok(include) => include,
+        Err(e) => return Response::Failure(e),
+    };
+
+    if let Err(e) = database
+        .get::<_, _, i64>(tenant_companies(tenant).select(company::company_id), company_id)
+        .await
+    {
+        return Response::Failure(e.into());
+    }
+
+    let updated_since = query.updated_since();
+
+    database
+        .run(move |connection| {
+            listed_vacancies(tenant, updated_since)
+                .filter(vacancy::company_id.eq(company_id))
+                .paginate(vacancy::vacancy_id, pagination)
+                .load_page(connection)?
+                .try_map(|vacancies| embed_relations(connection, vacancies, &include))
+        })
+        .await
+        .into_page_response(Status::Ok)
+}
+
+#[openapi(tag = "Vacancy")]
+#[post("/company/<company_id>/vacancy", data = "<new_vacancy>")]
+pub async fn add_new_company_vacancy(
+    company_id: i64,
+    new_vacancy: Validated<NewCompanyVacancy>,
+    tenant: Tenant,
+    database: Database,
+) -> Response<Vacancy> {
+    if let Err(e) = database
+        .get::<_, _, i64>(tenant_companies(tenant).select(company::company_id), company_id)
+        .await
+    {
+        return Response::Failure(e.into());
+    }
+
+    database
+        .create(
+            vacancy_table,
+            (
+                vacancy::jobboard_id.eq(tenant.jobboard_id()),
+                vacancy::company_id.eq(company_id),
+                new_vacancy.into_inner(),
+            ),
+        )
+        .await
+        .into_versioned_response(Status::Created)
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::form::Form;
+
+    use super::VacancyStatus::*;
+    use super::*;
+
+    const STATUSES: [VacancyStatus; 6] = [Draft, Published, Paused, Filled, Closed, Expired];
+
+    #[test]
+    fn vacancies_are_published_from_drafts_or_pauses() {
+        assert!(Draft.can_transition_to(Published));
+        assert!(Paused.can_transition_to(Published));
+        assert!(Published.can_transition_to(Paused));
+
+        assert!(!Draft.can_transition_to(Paused));
+        assert!(!Filled.can_transition_to(Published));
+        assert!(!Expired.can_transition_to(Published));
+    }
+
+    #[test]
+    fn only_open_vacancies_get_filled_or_expired() {
+        for status in STATUSES {
+            let open = matches!(status, Published | Paused);
+
+            assert_eq!(status.can_transition_to(Filled), open, "{} to filled", status);
+            assert_eq!(status.can_transition_to(Expired), open, "{} to expired", status);
+        }
+    }
+
+    #[test]
+    fn vacancies_can_be_closed_until_they_are() {
+        for status in [Draft, Published, Paused, Filled, Expired] {
+            assert!(status.can_transition_to(Closed), "{} should move to closed", status);
+        }
+
+        for next in STATUSES {
+            assert!(!Closed.can_transition_to(next), "closed should not move to {}", next);
+        }
+    }
+
+    fn filter(query: &str) -> VacancyFilter {
+        Form::<VacancyFilter>::parse(query).unwrap()
+    }
+
+    #[test]
+    fn filter_accepts_consistent_hours() {
+        for query in [
+            "",
+            "min_hours=20",
+            "max_hours=20",
+            "min_hours=20&max_hours=20",
+            "min_hours=20&max_hours=40",
+        ] {
+            assert!(filter(query).validate().is_ok(), "{}", query);
+        }
+    }
+
+    #[test]
+    fn filter_rejects_minimum_hours_above_maximum_hours() {
+        let result = filter("min_hours=40&max_hours=20").validate();
+
+        assert!(matches!(result, Err(Error::BadRequest(_))));
+    }
+
+    #[test]
+    fn filter_ignores_unknown_statuses() {
+        assert_eq!(filter("status=paused").status, Some(Paused));
+        assert_eq!(filter("status=archived").status, None);
+    }
+}
@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use diesel::r2d2::{ConnectionManager, Pool};
+use rocket::fairing::{AdHoc, Fairing};
+use rocket::tokio::{self, task, time};
+
+use crate::database::Connection;
+use crate::routes::apply_schedule;
+use crate::Config;
+
+/// Fairing spawning, on liftoff, the task which periodically publishes scheduled vacancies and expires overdue ones.
+///
+/// The task runs every `scheduler_interval` seconds of the [`Config`]. It gets a connection to the `main` database on
+/// each run, from a pool of its own holding a single connection, and skips the runs during which none is available.
+pub fn fairing() -> impl Fairing {
+    AdHoc::on_liftoff("Vacancy scheduler", |rocket| {
+        Box::pin(async move {
+            let period = rocket
+                .state::<Config>()
+                .map_or(60, |config| config.scheduler_interval)
+                .max(1);
+
+            let url = match rocket.figment().extract_inner::<String>("databases.main.url") {
+                Ok(url) => url,
+                Err(e) => {
+                    tracing::error!(error = %e, "Vacancy scheduler cannot start: database is not configured");
+                    return;
+                }
+            };
+            let pool = Pool::builder()
+                .max_size(1)
+                .connection_timeout(Duration::from_secs(5))
+                .build_unchecked(ConnectionManager::<Connection>::new(url));
+
+            tokio::spawn(async move {
+                let mut interval = time::interval(Duration::from_secs(period));
+
+                loop {
+                    interval.tick().await;
+
+                    let pool = pool.clone();
+                    let run = task::spawn_blocking(move || {
+                        let connection = match pool.get() {
+                            Ok(connection) => connection,
+                            Err(e) => {
+                                tracing::error!(error = %e, "Vacancy scheduler skipped a run: database is unreachable");
+                                return;
+                            }
+                        };
+
+                        match apply_schedule(&connection) {
+                            Ok((0, 0)) => {}
+                            Ok((published, expired)) => {
+                                tracing::info!(published, expired, "Vacancy scheduler applied the schedule")
+                            }
+                            Err(e) => tracing::error!(error = %e, "Vacancy scheduler failed"),
+                        }
+                    });
+
+                    if let Err(e) = run.await {
+                        tracing::error!(error = %e, "Vacancy scheduler panicked");
+                    }
+                }
+            });
+        })
+    })
+}
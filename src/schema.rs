@@ -12,6 +12,21 @@ table! {
         url_extra_3 -> Nullable<Varchar>,
         verified -> Bool,
         status -> Varchar,
+        version -> Int4,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        deleted_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    application_history (history_id) {
+        history_id -> Int8,
+        application_id -> Int8,
+        from_status -> Varchar,
+        to_status -> Varchar,
+        actor -> Varchar,
+        changed_at -> Timestamptz,
     }
 }
 
@@ -27,6 +42,10 @@ table! {
         timestamp -> Nullable<Timestamptz>,
         verified -> Bool,
         active -> Bool,
+        version -> Int4,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        deleted_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -36,10 +55,23 @@ table! {
         jobboard_name -> Varchar,
         url -> Nullable<Varchar>,
         account -> Varchar,
-        key -> Nullable<Varchar>,
         timestamp -> Nullable<Timestamptz>,
         verified -> Bool,
         active -> Bool,
+        version -> Int4,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        deleted_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    jobboard_key (key_id) {
+        key_id -> Varchar,
+        jobboard_id -> Int8,
+        key_hash -> Varchar,
+        created_at -> Timestamptz,
+        expires_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -63,13 +95,28 @@ table! {
         status -> Varchar,
         verified -> Bool,
         active -> Bool,
+        publish_at -> Nullable<Timestamptz>,
+        expires_at -> Nullable<Timestamptz>,
+        version -> Int4,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        deleted_at -> Nullable<Timestamptz>,
     }
 }
 
 joinable!(application -> jobboard (jobboard_id));
 joinable!(application -> vacancy (vacancy_id));
+joinable!(application_history -> application (application_id));
 joinable!(company -> jobboard (jobboard_id));
+joinable!(jobboard_key -> jobboard (jobboard_id));
 joinable!(vacancy -> company (company_id));
 joinable!(vacancy -> jobboard (jobboard_id));
 
-allow_tables_to_appear_in_same_query!(application, company, jobboard, vacancy,);
+allow_tables_to_appear_in_same_query!(
+    application,
+    application_history,
+    company,
+    jobboard,
+    jobboard_key,
+    vacancy,
+);
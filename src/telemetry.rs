@@ -0,0 +1,147 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::route::{Handler, Outcome};
+use rocket::{Data, Request, Response, Route};
+use serde::Deserialize;
+use tracing::field::{display, Empty};
+use tracing::{Instrument, Span};
+use tracing_subscriber::EnvFilter;
+use yansi::Paint;
+
+use crate::request_id::RequestId;
+
+/// Output of the logs, set by the `log_format` of the [`crate::Config`].
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable, multi-line output meant for development.
+    #[default]
+    Pretty,
+    /// One JSON object per line, meant for log collectors.
+    Json,
+}
+
+/// Installs the global `tracing` subscriber, filtered by the `RUST_LOG` environment variable (`info` by default).
+///
+/// Records of the `log` crate, including the ones of Rocket, are forwarded to the subscriber, which must therefore be
+/// installed before building Rocket. Rocket then leaves its colours on, which are turned off for JSON logs.
+pub fn init(format: LogFormat) -> Result<(), eyre::Report> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+
+    match format {
+        LogFormat::Pretty => subscriber.pretty().try_init(),
+        LogFormat::Json => {
+            Paint::disable();
+            subscriber.json().with_span_list(true).try_init()
+        }
+    }
+    .map_err(|e| eyre::eyre!(e))
+}
+
+/// Span of a request, along with what is measured while it is processed.
+struct RequestSpan {
+    span: Span,
+    start: Instant,
+    /// Time spent waiting for the database, in microseconds.
+    database_time: Arc<AtomicU64>,
+}
+
+impl RequestSpan {
+    fn of<'r>(request: &'r Request<'_>) -> &'r Self {
+        request.local_cache(|| Self {
+            span: tracing::info_span!(
+                "request",
+                request_id = %RequestId::of(request),
+                method = %request.method(),
+                uri = %request.uri(),
+                route = Empty,
+                status = Empty,
+                latency_ms = Empty,
+                database_ms = Empty,
+                jobboard_id = Empty,
+            ),
+            start: Instant::now(),
+            database_time: Arc::default(),
+        })
+    }
+}
+
+/// Counter of the time the request spends waiting for the database, shared with its [`crate::Database`].
+pub(crate) fn database_time(request: &Request<'_>) -> Arc<AtomicU64> {
+    RequestSpan::of(request).database_time.clone()
+}
+
+pub(crate) fn add_database_time(database_time: &AtomicU64, elapsed: Duration) {
+    database_time.fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+}
+
+/// Tags the request with the jobboard which sent it.
+pub(crate) fn record_jobboard(request: &Request<'_>, jobboard_id: i64) {
+    RequestSpan::of(request).span.record("jobboard_id", jobboard_id);
+}
+
+/// Runs the handlers of the `routes` within the span of their request, so that the events they emit are tagged with it.
+pub fn traced(routes: Vec<Route>) -> Vec<Route> {
+    routes
+        .into_iter()
+        .map(|mut route| {
+            route.handler = Box::new(TracedHandler(route.handler));
+            route
+        })
+        .collect()
+}
+
+#[derive(Clone)]
+struct TracedHandler(Box<dyn Handler>);
+
+#[rocket::async_trait]
+impl Handler for TracedHandler {
+    async fn handle<'r>(&self, request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r> {
+        let span = RequestSpan::of(request).span.clone();
+        self.0.handle(request, data).instrument(span).await
+    }
+}
+
+/// Fairing tracing every request in a span holding its method, route, status, latency, jobboard and database time,
+/// which is logged once the response is ready.
+pub fn fairing() -> impl Fairing {
+    TelemetryFairing
+}
+
+struct TelemetryFairing;
+
+#[rocket::async_trait]
+impl Fairing for TelemetryFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request tracing",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _data: &mut Data<'_>) {
+        // Started upfront so that the latency covers the whole processing of the request.
+        RequestSpan::of(request);
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let RequestSpan {
+            span,
+            start,
+            database_time,
+        } = RequestSpan::of(request);
+
+        if let Some(route) = request.route() {
+            span.record("route", display(&route.uri));
+        }
+        span.record("status", response.status().code);
+        span.record("latency_ms", start.elapsed().as_secs_f64() * 1000.0);
+        span.record("database_ms", database_time.load(Ordering::Relaxed) as f64 / 1000.0);
+
+        tracing::info!(parent: span, "Request completed");
+    }
+}
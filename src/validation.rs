@@ -0,0 +1,379 @@
+use chrono::{DateTime, Utc};
+use rocket::data::{self, Data, FromData};
+use rocket::outcome::Outcome;
+use rocket::serde::json::{Error as JsonError, Json};
+use rocket::Request;
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::openapi3::RequestBody;
+use rocket_okapi::request::OpenApiFromData;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use url::Url;
+
+use crate::error::reject;
+use crate::Error;
+
+/// Invalid field of a request body, reported so that clients can point at the offending input.
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct FieldError {
+    field: String,
+    /// Machine-readable reason, such as `required`, `unknown_field`, `invalid`, `too_long`, `invalid_email`,
+    /// `invalid_url`, `too_large` or `empty`.
+    code: &'static str,
+    message: String,
+}
+
+impl FieldError {
+    pub(crate) fn new(field: impl Into<String>, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Request body checked beyond its JSON format, every invalid field being reported at once.
+pub trait Validate {
+    /// Records the invalid fields of the body into the `validator`, bodies without constraints having none.
+    fn validate(&self, _validator: &mut Validator) {}
+}
+
+/// Collects the invalid fields of a request body.
+#[derive(Debug, Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    /// Records the `field` as invalid for the given reason.
+    pub fn invalid(&mut self, field: &'static str, code: &'static str, message: impl Into<String>) -> &mut Self {
+        self.errors.push(FieldError::new(field, code, message));
+        self
+    }
+
+    pub fn required(&mut self, field: &'static str, value: &impl Field<str>) -> &mut Self {
+        match value.value() {
+            Some(value) if value.trim().is_empty() => self.invalid(field, "required", "Must not be empty"),
+            _ => self,
+        }
+    }
+
+    /// Checks that the value fits in a `VARCHAR(max)` column.
+    pub fn max_length(&mut self, field: &'static str, value: &impl Field<str>, max: usize) -> &mut Self {
+        match value.value() {
+            Some(value) if value.chars().count() > max => {
+                self.invalid(field, "too_long", format!("Must be at most {} characters long", max))
+            }
+            _ => self,
+        }
+    }
+
+    pub fn email(&mut self, field: &'static str, value: &impl Field<str>) -> &mut Self {
+        match value.value() {
+            Some(value) if !is_email(value) => self.invalid(field, "invalid_email", "Must be a valid email address"),
+            _ => self,
+        }
+    }
+
+    /// Checks that the value is an absolute `http` or `https` URL.
+    pub fn url(&mut self, field: &'static str, value: &impl Field<str>) -> &mut Self {
+        match value.value() {
+            Some(value) if !is_url(value) => self.invalid(field, "invalid_url", "Must be a valid http or https URL"),
+            _ => self,
+        }
+    }
+
+    pub fn max(&mut self, field: &'static str, value: &impl Field<i16>, max: i16) -> &mut Self {
+        match value.value() {
+            Some(value) if *value > max => self.invalid(field, "too_large", format!("Must be at most {}", max)),
+            _ => self,
+        }
+    }
+
+    /// Checks that a partial update changes at least one field, the body itself being reported when it is empty.
+    pub fn not_empty(&mut self, is_empty: bool) -> &mut Self {
+        if is_empty {
+            self.invalid(".", "empty", "Must change at least one field")
+        } else {
+            self
+        }
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidFields(self.errors))
+        }
+    }
+}
+
+fn is_email(value: &str) -> bool {
+    match value.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !value.contains(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
+fn is_url(value: &str) -> bool {
+    matches!(Url::parse(value), Ok(url) if matches!(url.scheme(), "http" | "https") && url.has_host())
+}
+
+/// Field of a request body, which is only validated when it holds a value. Nullable fields of partial updates are
+/// therefore validated when they are neither missing nor `null`.
+pub trait Field<T: ?Sized> {
+    fn value(&self) -> Option<&T>;
+}
+
+impl Field<str> for String {
+    fn value(&self) -> Option<&str> {
+        Some(self)
+    }
+}
+
+impl Field<str> for Option<String> {
+    fn value(&self) -> Option<&str> {
+        self.as_deref()
+    }
+}
+
+impl Field<str> for Option<Option<String>> {
+    fn value(&self) -> Option<&str> {
+        self.as_ref().and_then(Option::as_deref)
+    }
+}
+
+impl Field<i16> for Option<i16> {
+    fn value(&self) -> Option<&i16> {
+        self.as_ref()
+    }
+}
+
+impl Field<i16> for Option<Option<i16>> {
+    fn value(&self) -> Option<&i16> {
+        self.as_ref().and_then(Option::as_ref)
+    }
+}
+
+impl Field<DateTime<Utc>> for Option<DateTime<Utc>> {
+    fn value(&self) -> Option<&DateTime<Utc>> {
+        self.as_ref()
+    }
+}
+
+impl Field<DateTime<Utc>> for Option<Option<DateTime<Utc>>> {
+    fn value(&self) -> Option<&DateTime<Utc>> {
+        self.as_ref().and_then(Option::as_ref)
+    }
+}
+
+/// Tells which field of the JSON body does not match the expected resource `T`, the body being deserialized again
+/// while tracking the path of the field at fault.
+fn mismatched_field<T: DeserializeOwned>(json: &str, e: &serde_json::Error) -> FieldError {
+    let path = match serde_path_to_error::deserialize::<_, T>(&mut serde_json::Deserializer::from_str(json)) {
+        Err(e) => e.path().to_string(),
+        Ok(_) => ".".to_string(),
+    };
+
+    // Messages of serde end with the position of the error, which the field makes redundant.
+    let message = e.to_string();
+    let message = message
+        .rsplit_once(" at line ")
+        .map_or(message.as_str(), |(message, _)| message);
+
+    // Missing fields are reported on the object holding them, the name of the field being quoted. So may be unknown
+    // fields, depending on whether their name has been read when failing.
+    let child = |name: &str| match path.as_str() {
+        "." => name.to_string(),
+        path if path == name || path.ends_with(&format!(".{}", name)) => path.to_string(),
+        path => format!("{}.{}", path, name),
+    };
+
+    match message.split('`').nth(1) {
+        Some(name) if message.starts_with("missing field") => FieldError::new(child(name), "required", "Is required"),
+        Some(name) if message.starts_with("unknown field") => {
+            FieldError::new(child(name), "unknown_field", "Is not an expected field")
+        }
+        _ => FieldError::new(path.as_str(), "invalid", message),
+    }
+}
+
+/// JSON request body which has been validated.
+///
+/// Rejected bodies are kept in the request cache so that the catchers can report why they were rejected.
+#[derive(Debug)]
+pub struct Validated<T>(T);
+
+impl<T> Validated<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, T> FromData<'r> for Validated<T>
+where
+    T: DeserializeOwned + Validate,
+{
+    type Error = Error;
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let error = match Json::<T>::from_data(request, data).await {
+            Outcome::Success(Json(body)) => {
+                let mut validator = Validator::default();
+                body.validate(&mut validator);
+
+                match validator.finish() {
+                    Ok(()) => return Outcome::Success(Self(body)),
+                    Err(e) => e,
+                }
+            }
+            Outcome::Failure((_, JsonError::Parse(json, e))) if e.is_data() => {
+                Error::InvalidFields(vec![mismatched_field::<T>(json, &e)])
+            }
+            Outcome::Failure((_, e)) => e.into(),
+            Outcome::Forward(data) => return Outcome::Forward(data),
+        };
+
+        reject(request, error)
+    }
+}
+
+impl<'r, T> OpenApiFromData<'r> for Validated<T>
+where
+    T: DeserializeOwned + JsonSchema + Validate,
+{
+    fn request_body(generator: &mut OpenApiGenerator) -> rocket_okapi::Result<RequestBody> {
+        Json::<T>::request_body(generator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[test]
+    fn is_email_accepts_addresses() {
+        for value in ["jane@example.com", "jane.doe+jobs@mail.example.org"] {
+            assert!(is_email(value), "{} should be accepted", value);
+        }
+    }
+
+    #[test]
+    fn is_email_rejects_malformed_addresses() {
+        for value in [
+            "",
+            "jane",
+            "@example.com",
+            "jane@",
+            "jane@example",
+            "jane@.com",
+            "jane@example.",
+            "jane@a@b.com",
+        ] {
+            assert!(!is_email(value), "{} should be rejected", value);
+        }
+        assert!(!is_email("jane doe@example.com"));
+    }
+
+    #[test]
+    fn is_url_accepts_http_urls() {
+        for value in ["http://example.com", "https://example.com/jobs?id=1"] {
+            assert!(is_url(value), "{} should be accepted", value);
+        }
+    }
+
+    #[test]
+    fn is_url_rejects_other_urls() {
+        for value in [
+            "",
+            "example.com",
+            "/jobs",
+            "ftp://example.com",
+            "mailto:jane@example.com",
+            "https://",
+        ] {
+            assert!(!is_url(value), "{} should be rejected", value);
+        }
+    }
+
+    #[test]
+    fn validator_collects_every_invalid_field() {
+        let mut validator = Validator::default();
+        validator
+            .required("name", &" ".to_string())
+            .max_length("description", &Some("abcd".to_string()), 3)
+            .max("commission", &Some(Some(10001)), 10000)
+            .email("email", &None::<String>)
+            .url("url", &Some(None::<String>));
+
+        let codes = validator.errors.iter().map(|error| error.code).collect::<Vec<_>>();
+        assert_eq!(codes, vec!["required", "too_long", "too_large"]);
+        assert!(matches!(validator.finish(), Err(Error::InvalidFields(errors)) if errors.len() == 3));
+    }
+
+    #[test]
+    fn validator_rejects_empty_partial_updates() {
+        let mut validator = Validator::default();
+        validator.not_empty(false);
+        assert!(validator.finish().is_ok());
+
+        let mut validator = Validator::default();
+        validator.not_empty(true);
+        assert!(matches!(
+            validator.finish(),
+            Err(Error::InvalidFields(errors)) if errors[0].field == "." && errors[0].code == "empty"
+        ));
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    #[allow(dead_code)]
+    struct Body {
+        last_name: String,
+        positions: Option<i16>,
+    }
+
+    fn mismatched(json: &str) -> FieldError {
+        let e = serde_json::from_str::<Body>(json).unwrap_err();
+
+        mismatched_field::<Body>(json, &e)
+    }
+
+    #[test]
+    fn mismatched_field_reports_missing_fields() {
+        let error = mismatched(r#"{"positions": 2}"#);
+
+        assert_eq!((error.field.as_str(), error.code), ("last_name", "required"));
+    }
+
+    #[test]
+    fn mismatched_field_reports_unknown_fields() {
+        let error = mismatched(r#"{"last_name": "Doe", "salary": 1}"#);
+
+        assert_eq!((error.field.as_str(), error.code), ("salary", "unknown_field"));
+    }
+
+    #[test]
+    fn mismatched_field_reports_mistyped_fields() {
+        let error = mismatched(r#"{"last_name": "Doe", "positions": "two"}"#);
+
+        assert_eq!((error.field.as_str(), error.code), ("positions", "invalid"));
+        assert!(!error.message.contains("line"));
+    }
+}
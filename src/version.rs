@@ -0,0 +1,154 @@
+use chrono::{DateTime, Utc};
+use rocket::outcome::Outcome;
+use rocket::request::{self, FromRequest, Request};
+use rocket_okapi::gen::OpenApiGenerator;
+use rocket_okapi::okapi::openapi3::{Object, Parameter, ParameterValue};
+use rocket_okapi::request::{OpenApiFromRequest, RequestHeaderInput};
+
+use crate::error::reject;
+use crate::Error;
+
+const IF_MATCH_HEADER: &str = "If-Match";
+
+/// Resource carrying a version, which is bumped on every update and sent as the `ETag` of the resource.
+pub trait Versioned {
+    fn version(&self) -> i32;
+
+    /// Time of the last change of the resource, sent as its `Last-Modified` when known.
+    fn last_modified(&self) -> Option<DateTime<Utc>> {
+        None
+    }
+}
+
+/// Fails when the resource is not at the `expected` version, which rolls back the enclosing transaction if any.
+pub(crate) fn check_version(expected: i32, actual: i32) -> Result<(), Error> {
+    check(expected == actual)
+}
+
+/// Same as [`check_version`] for a resource which has just been updated, every update bumping its version by one: any
+/// other version means a concurrent update went first.
+pub(crate) fn check_bumped_version(expected: i32, actual: i32) -> Result<(), Error> {
+    // The expected version comes from the client, hence it is the actual one which is decremented to avoid overflows.
+    check(actual.checked_sub(1) == Some(expected))
+}
+
+fn check(unchanged: bool) -> Result<(), Error> {
+    if unchanged {
+        Ok(())
+    } else {
+        Err(Error::PreconditionFailed(
+            "Resource has changed since it was fetched".to_string(),
+        ))
+    }
+}
+
+/// Version of the resource the caller expects to change, read from the `If-Match` header.
+///
+/// The header holds the `ETag` previously received along with the resource and is required to change it, so that
+/// concurrent changes do not silently overwrite each other.
+#[derive(Clone, Copy, Debug)]
+pub struct IfMatch {
+    version: i32,
+}
+
+impl IfMatch {
+    pub fn version(&self) -> i32 {
+        self.version
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfMatch {
+    type Error = Error;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let error = match request.headers().get_one(IF_MATCH_HEADER).map(str::trim) {
+            Some(etag) => match parse_etag(etag) {
+                Some(version) => return Outcome::Success(Self { version }),
+                None => Error::BadRequest(format!(
+                    "Invalid {} header '{}': expected a strong ETag such as \"3\"",
+                    IF_MATCH_HEADER, etag
+                )),
+            },
+            None => Error::PreconditionRequired(format!(
+                "{} header is required to change this resource",
+                IF_MATCH_HEADER
+            )),
+        };
+
+        reject(request, error)
+    }
+}
+
+/// Version held by a strong `ETag`, such as `"3"`.
+fn parse_etag(etag: &str) -> Option<i32> {
+    etag.strip_prefix('"')?.strip_suffix('"')?.parse().ok()
+}
+
+impl<'r> OpenApiFromRequest<'r> for IfMatch {
+    fn from_request_input(
+        gen: &mut OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        Ok(RequestHeaderInput::Parameter(Parameter {
+            name: IF_MATCH_HEADER.to_string(),
+            location: "header".to_string(),
+            description: Some("`ETag` of the resource as last fetched, e.g. `\"3\"`.".to_string()),
+            required: true,
+            deprecated: false,
+            allow_empty_value: false,
+            value: ParameterValue::Schema {
+                style: None,
+                explode: None,
+                allow_reserved: false,
+                schema: gen.json_schema::<String>(),
+                example: None,
+                examples: None,
+            },
+            extensions: Object::default(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_etag_reads_strong_etags() {
+        assert_eq!(parse_etag("\"3\""), Some(3));
+        assert_eq!(parse_etag("\"2147483647\""), Some(i32::MAX));
+    }
+
+    #[test]
+    fn parse_etag_rejects_malformed_etags() {
+        for etag in [
+            "3",
+            "\"3",
+            "3\"",
+            "\"\"",
+            "\"",
+            "W/\"3\"",
+            "\"three\"",
+            "\"2147483648\"",
+            "*",
+        ] {
+            assert_eq!(parse_etag(etag), None, "{} should be rejected", etag);
+        }
+    }
+
+    #[test]
+    fn check_version_compares_versions() {
+        assert!(check_version(3, 3).is_ok());
+        assert!(matches!(check_version(3, 4), Err(Error::PreconditionFailed(_))));
+    }
+
+    #[test]
+    fn check_bumped_version_expects_the_next_version() {
+        assert!(check_bumped_version(3, 4).is_ok());
+        assert!(check_bumped_version(3, 3).is_err());
+        assert!(check_bumped_version(3, 5).is_err());
+        assert!(check_bumped_version(i32::MAX, i32::MIN).is_err());
+    }
+}